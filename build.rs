@@ -1,14 +1,276 @@
 #[cfg(feature = "ffi")]
 fn main() {
     println!("cargo:rerun-if-changed=lib/ckytea.cpp");
-    println!("cargo:rustc-link-lib=kytea");
-    cc::Build::new()
+
+    let mut build = cc::Build::new();
+    build
         .cpp(true)
         .file("lib/ckytea.cpp")
         .flag("-Wno-deprecated")
-        .cpp_link_stdlib("stdc++")
-        .compile("libckytea.a");
+        .cpp_link_stdlib("stdc++");
+
+    #[cfg(feature = "vendored")]
+    let blas = vendored::add_kytea(&mut build);
+    #[cfg(not(feature = "vendored"))]
+    let kytea = system::find_kytea(&mut build);
+
+    build.compile("libckytea.a");
+
+    // Linked after the shim, which matters when KyTea is a static library.
+    #[cfg(not(feature = "vendored"))]
+    kytea.link();
+
+    // Compiled after the shim so that it is linked after KyTea, which depends on it.
+    #[cfg(feature = "vendored")]
+    blas.compile("libkytea-blas.a");
 }
 
 #[cfg(not(feature = "ffi"))]
 fn main() {}
+
+/// Finds an installed KyTea from the environment variables or pkg-config.
+///
+/// - `KYTEA_INCLUDE_DIR`: the directory containing `kytea/kytea.h`.
+/// - `KYTEA_LIB_DIR`: the directory containing `libkytea`.
+/// - `KYTEA_STATIC`: links `libkytea.a` if set to anything but `0`.
+///
+/// pkg-config is used only if neither directory is given.
+#[cfg(all(feature = "ffi", not(feature = "vendored")))]
+mod system {
+    use std::env;
+    use std::path::PathBuf;
+    use std::process::Stdio;
+
+    /// Common directories searched by the compiler without `-I`; the others are found by
+    /// compiling the `#include`.
+    const DEFAULT_INCLUDE_DIRS: &[&str] = &["/usr/include", "/usr/local/include"];
+
+    pub struct Kytea {
+        link_paths: Vec<PathBuf>,
+        libs: Vec<String>,
+        statik: bool,
+        /// Where the default model is searched by `ModelResolver`.
+        prefix: Option<PathBuf>,
+    }
+
+    impl Kytea {
+        pub fn link(&self) {
+            if let Some(prefix) = &self.prefix {
+                println!("cargo:rustc-env=KYTEA_PREFIX={}", prefix.display());
+            }
+            for path in &self.link_paths {
+                println!("cargo:rustc-link-search=native={}", path.display());
+            }
+            for lib in &self.libs {
+                if self.statik && lib == "kytea" {
+                    println!("cargo:rustc-link-lib=static={}", lib);
+                } else {
+                    println!("cargo:rustc-link-lib={}", lib);
+                }
+            }
+        }
+    }
+
+    pub fn find_kytea(build: &mut cc::Build) -> Kytea {
+        for var in ["KYTEA_INCLUDE_DIR", "KYTEA_LIB_DIR", "KYTEA_STATIC"] {
+            println!("cargo:rerun-if-env-changed={}", var);
+        }
+        let statik = env::var_os("KYTEA_STATIC").is_some_and(|s| s != "0");
+        let include_dir = env::var_os("KYTEA_INCLUDE_DIR").map(PathBuf::from);
+        let lib_dir = env::var_os("KYTEA_LIB_DIR").map(PathBuf::from);
+
+        let mut pkg_config_error = None;
+        if include_dir.is_none() && lib_dir.is_none() {
+            match pkg_config::Config::new()
+                .statik(statik)
+                .cargo_metadata(false)
+                .probe("kytea")
+            {
+                Ok(lib) => {
+                    build.includes(&lib.include_paths);
+                    check_headers(build, &lib.include_paths, None);
+                    return Kytea {
+                        link_paths: lib.link_paths,
+                        libs: lib.libs,
+                        statik,
+                        prefix: pkg_config::get_variable("kytea", "prefix")
+                            .ok()
+                            .map(PathBuf::from),
+                    };
+                }
+                Err(e) => pkg_config_error = Some(e),
+            }
+        }
+
+        let include_dirs = include_dir.into_iter().collect::<Vec<_>>();
+        build.includes(&include_dirs);
+        check_headers(build, &include_dirs, pkg_config_error);
+        Kytea {
+            prefix: lib_dir
+                .as_deref()
+                .and_then(|dir| dir.parent())
+                .map(PathBuf::from),
+            link_paths: lib_dir.into_iter().collect(),
+            libs: vec![String::from("kytea")],
+            statik,
+        }
+    }
+
+    /// Fails early with instructions instead of the compiler error on `#include`.
+    fn check_headers(
+        build: &cc::Build,
+        include_dirs: &[PathBuf],
+        pkg_config_error: Option<pkg_config::Error>,
+    ) {
+        let env_dirs = ["CPATH", "CPLUS_INCLUDE_PATH"]
+            .iter()
+            .filter_map(env::var_os)
+            .flat_map(|paths| env::split_paths(&paths).collect::<Vec<_>>());
+        let dirs = include_dirs
+            .iter()
+            .cloned()
+            .chain(env_dirs)
+            .chain(DEFAULT_INCLUDE_DIRS.iter().map(PathBuf::from))
+            .collect::<Vec<_>>();
+        if dirs.iter().any(|dir| dir.join("kytea/kytea.h").is_file()) {
+            return;
+        }
+        match compile_header(build) {
+            Ok(true) => return,
+            Ok(false) => {}
+            Err(e) => {
+                println!(
+                    "cargo:warning=cannot check the KyTea headers with the compiler: {}",
+                    e
+                );
+                return;
+            }
+        }
+
+        let searched = dirs
+            .iter()
+            .map(|dir| format!("  {}", dir.display()))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let pkg_config = match pkg_config_error {
+            Some(e) => format!("\npkg-config did not find KyTea either:\n{}\n", e),
+            None => String::new(),
+        };
+        panic!(
+            "cannot find the KyTea headers (kytea/kytea.h) in:\n{}\n{}\n\
+             Install KyTea, then either\n\
+             - set KYTEA_INCLUDE_DIR and KYTEA_LIB_DIR (e.g. $PREFIX/include and $PREFIX/lib),\n\
+             - add the directory of kytea.pc to PKG_CONFIG_PATH, or\n\
+             - enable the `vendored` feature to compile KyTea from its sources.",
+            searched, pkg_config,
+        );
+    }
+
+    /// Whether the compiler finds `kytea/kytea.h`, e.g. in a directory it searches by default.
+    fn compile_header(build: &cc::Build) -> std::io::Result<bool> {
+        let out_dir = env::var_os("OUT_DIR")
+            .map(PathBuf::from)
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "OUT_DIR is unset"))?;
+        let probe = out_dir.join("kytea_header.cpp");
+        std::fs::write(&probe, "#include <kytea/kytea.h>\n")?;
+
+        let compiler = build.try_get_compiler().map_err(std::io::Error::other)?;
+        let syntax_only = if compiler.is_like_msvc() {
+            "/Zs"
+        } else {
+            "-fsyntax-only"
+        };
+        let status = compiler
+            .to_command()
+            .arg(syntax_only)
+            .arg(&probe)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()?;
+        Ok(status.success())
+    }
+}
+
+/// Compiles KyTea itself into `libckytea.a` instead of linking the system `libkytea`; neither
+/// pkg-config nor the `KYTEA_*` variables of an installed KyTea are used.
+#[cfg(feature = "vendored")]
+mod vendored {
+    use std::path::{Path, PathBuf};
+
+    /// A checkout of <https://github.com/neubig/kytea>, packaged with the crate under `lib/`.
+    const DEFAULT_SRC_DIR: &str = "lib/kytea";
+
+    /// Where the default model is looked up, as `make install` of KyTea would put it; the model
+    /// itself is not bundled.
+    const PREFIX: &str = "/usr/local";
+
+    /// Adds the sources of KyTea to `build` and returns the build of its C sources (BLAS).
+    pub fn add_kytea(build: &mut cc::Build) -> cc::Build {
+        println!("cargo:rerun-if-env-changed=KYTEA_SRC_DIR");
+        let src_dir = std::env::var_os("KYTEA_SRC_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_SRC_DIR));
+        let lib_dir = src_dir.join("src/lib");
+        let include_dir = src_dir.join("src/include");
+        check_sources(&src_dir, &lib_dir, &include_dir);
+        println!("cargo:rerun-if-changed={}", lib_dir.display());
+        println!("cargo:rerun-if-changed={}", include_dir.display());
+        println!("cargo:rustc-env=KYTEA_PREFIX={}", PREFIX);
+
+        let liblinear_dir = lib_dir.join("liblinear");
+        build
+            .include(&include_dir)
+            .include(&lib_dir)
+            .include(&liblinear_dir)
+            .define(
+                "PKGDATADIR",
+                Some(format!("\"{}/share/kytea\"", PREFIX).as_str()),
+            )
+            .flag_if_supported("-Wno-unused-parameter")
+            .flag_if_supported("-Wno-sign-compare")
+            .files(sources(&lib_dir, "cpp"))
+            .files(sources(&liblinear_dir, "cpp"));
+
+        let mut blas = cc::Build::new();
+        blas.include(&liblinear_dir)
+            .files(sources(&liblinear_dir.join("blas"), "c"))
+            .warnings(false);
+        blas
+    }
+
+    /// Fails early with instructions if the checkout is missing or is not KyTea's, instead of
+    /// the compiler errors on the first missing file.
+    fn check_sources(src_dir: &Path, lib_dir: &Path, include_dir: &Path) {
+        let expected = [
+            include_dir.join("kytea/kytea.h"),
+            lib_dir.join("kytea.cpp"),
+            lib_dir.join("liblinear/linear.cpp"),
+            lib_dir.join("liblinear/blas/blas.h"),
+        ];
+        let missing = expected
+            .iter()
+            .filter(|path| !path.is_file())
+            .map(|path| format!("  {}", path.display()))
+            .collect::<Vec<_>>();
+        if !missing.is_empty() {
+            panic!(
+                "the `vendored` feature needs the KyTea sources in {}, but these are missing:\n{}\n\
+                 Clone https://github.com/neubig/kytea into {} or set KYTEA_SRC_DIR to a checkout.",
+                src_dir.display(),
+                missing.join("\n"),
+                DEFAULT_SRC_DIR,
+            );
+        }
+    }
+
+    /// Files with the extension `ext` directly under `dir`, sorted for reproducible builds.
+    fn sources(dir: &Path, ext: &str) -> Vec<PathBuf> {
+        let mut files = std::fs::read_dir(dir)
+            .unwrap_or_else(|e| panic!("cannot read {}: {}", dir.display(), e))
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|e| e == ext))
+            .collect::<Vec<_>>();
+        files.sort();
+        files
+    }
+}
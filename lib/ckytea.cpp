@@ -1,16 +1,104 @@
+#include <cstdlib>
+#include <cstring>
+#include <exception>
 #include <iostream>
+#include <set>
 #include <strstream>
 #include <sstream>
+#include <string>
+#include <utility>
+#include <vector>
 
 #include <kytea/corpus-io.h>
+#include <kytea/dictionary.h>
 #include <kytea/kytea.h>
+#include <kytea/kytea-model.h>
 #include <kytea/kytea-struct.h>
 #include <kytea/string-util.h>
 
 using namespace std;
 using namespace kytea;
 
-extern "C" void *new_kytea(const char *model) {
+extern "C" typedef struct {
+    int code;
+    char *message;
+} Error;
+
+enum {
+    ERROR_NONE = 0,
+    ERROR_NOT_FOUND = 1,
+    ERROR_BAD_FORMAT = 2,
+    ERROR_UNSUPPORTED_VERSION = 3,
+    ERROR_IO = 4,
+    ERROR_KYTEA = 5,
+};
+
+// The message is allocated by malloc and freed by Rust.
+void set_error(Error *err, int code, const char *message) {
+    err->code = code;
+    err->message = strdup(message);
+}
+
+// KyTea reports every failure as a plain runtime_error, so we can only look at the message.
+void set_error_from(Error *err, const exception &e, int fallback) {
+    string message(e.what());
+    int code = fallback;
+    if(message.find("ould not open") != string::npos)
+        code = ERROR_NOT_FOUND;
+    else if(message.find("version") != string::npos)
+        code = ERROR_UNSUPPORTED_VERSION;
+    set_error(err, code, e.what());
+}
+
+#define CATCH_KYTEA(err, fallback)                              \
+    catch(const ios_base::failure &e) {                         \
+        set_error(err, ERROR_IO, e.what());                     \
+    }                                                           \
+    catch(const exception &e) {                                 \
+        set_error_from(err, e, fallback);                       \
+    }                                                           \
+    catch(...) {                                                \
+        set_error(err, fallback, "unknown exception in KyTea"); \
+    }
+
+extern "C" typedef struct {
+    int do_ws;
+    int do_tags;
+    const unsigned char *do_tag;
+    size_t num_do_tag;
+    // The options below are left to the model if NULL or negative.
+    const char *unk_tag;
+    int tag_max;
+    int unk_beam;
+    const char *default_tag;
+    const char *const *dictionaries;
+    size_t num_dictionaries;
+} Config;
+
+// Applied before reading the model, which loads the dictionaries together, so their words are
+// added to the dictionary of the model and known to the tagger.
+void apply_dictionaries(KyteaConfig *config, const Config *rust_config) {
+    for(size_t i = 0; i < rust_config->num_dictionaries; i++)
+        config->addDictionary(rust_config->dictionaries[i]);
+}
+
+// Applied after reading the model, which overwrites the number of tags.
+void apply_config(KyteaConfig *config, const Config *rust_config) {
+    config->setDoWS(rust_config->do_ws);
+    config->setDoTags(rust_config->do_tags);
+    for(size_t i = 0; i < rust_config->num_do_tag; i++)
+        config->setDoTag(i, rust_config->do_tag[i]);
+    if(rust_config->unk_tag)
+        config->setUnkTag(rust_config->unk_tag);
+    if(rust_config->tag_max >= 0)
+        config->setTagMax(rust_config->tag_max);
+    if(rust_config->unk_beam >= 0)
+        config->setUnkBeam(rust_config->unk_beam);
+    if(rust_config->default_tag)
+        config->setDefaultTag(rust_config->default_tag);
+}
+
+extern "C" void *new_kytea(const char *model, const Config *rust_config, Error *err) {
     Kytea *kytea;
     kytea = new Kytea;
 
@@ -20,7 +108,16 @@ extern "C" void *new_kytea(const char *model) {
     config->setWordBound("\t");
     config->setInputFormat(CORP_FORMAT_RAW);
 
-    kytea->readModel(model);
+    try {
+        apply_dictionaries(config, rust_config);
+        kytea->readModel(model);
+        apply_config(config, rust_config);
+    } CATCH_KYTEA(err, ERROR_BAD_FORMAT)
+
+    if(err->code != ERROR_NONE) {
+        delete kytea;
+        return 0;
+    }
 
     return (void *)kytea;
 }
@@ -35,6 +132,12 @@ extern "C" void *new_istream(char *input) {
     return (void *)buf;
 }
 
+// The input may contain nul bytes.
+extern "C" void *new_istream_with_len(const char *input, size_t len) {
+    stringstream *buf = new stringstream(string(input, len), ios_base::in);
+    return (void *)buf;
+}
+
 extern "C" void delete_istream(void *void_stream) {
     stringstream *buf = (stringstream *)void_stream;
     delete buf;
@@ -70,7 +173,42 @@ extern "C" Str ostream_rust(void *void_stream) {
     return str;
 }
 
-void run_kytea_with_io(Kytea *kytea, CorpusIO *in, CorpusIO *out) {
+// Deletes the IOs even if KyTea throws in the middle of the analysis.
+struct IOGuard {
+    CorpusIO *in;
+    CorpusIO *out;
+
+    IOGuard() : in(0), out(0) {}
+    ~IOGuard() {
+        delete in;
+        delete out;
+    }
+};
+
+void analyze_sentence(Kytea *kytea, KyteaSentence *sentence) {
+    KyteaConfig *config = kytea->getConfig();
+
+    if(config->getDoWS())
+        kytea->calculateWS(*sentence);
+    if(config->getDoTags())
+        for(int i = 0; i < config->getNumTags(); i++)
+            if(config->getDoTag(i))
+                kytea->calculateTags(*sentence, i);
+}
+
+// Same order as `OutputFormat` in Rust; any other value is the default of the config.
+CorpusFormat output_format(KyteaConfig *config, int format) {
+    switch(format) {
+        case 0: return CORP_FORMAT_FULL;
+        case 1: return CORP_FORMAT_TOK;
+        case 2: return CORP_FORMAT_PART;
+        case 3: return CORP_FORMAT_PROB;
+        case 4: return CORP_FORMAT_EDA;
+        default: return config->getOutputFormat();
+    }
+}
+
+void prepare_output(Kytea *kytea, CorpusIO *out) {
     KyteaConfig *config = kytea->getConfig();
 
     out->setUnkTag(config->getUnkTag());
@@ -78,88 +216,513 @@ void run_kytea_with_io(Kytea *kytea, CorpusIO *in, CorpusIO *out) {
 
     for(int i = 0; i < config->getNumTags(); i++)
         out->setDoTag(i, config->getDoTag(i));
+}
+
+void run_kytea_with_io(Kytea *kytea, CorpusIO *in, CorpusIO *out) {
+    prepare_output(kytea, out);
 
     KyteaSentence* next;
     while((next = in->readSentence()) != 0) {
-        if(config->getDoWS())
-            kytea->calculateWS(*next);
-        if(config->getDoTags())
-            for(int i = 0; i < config->getNumTags(); i++)
-                if(config->getDoTag(i))
-                    kytea->calculateTags(*next, i);
+        analyze_sentence(kytea, next);
         out->writeSentence(next);
         delete next;
     }
 }
 
-extern "C" void run_kytea_str_str(void *void_kytea, void *input, void *output) {
+extern "C" void run_kytea_str_str(void *void_kytea, void *input, void *output, int format, Error *err) {
     Kytea *kytea = (Kytea *)void_kytea;
 
     StringUtil *util = kytea->getStringUtil(); 
 
     KyteaConfig *config = kytea->getConfig();
 
-    CorpusIO *in, *out;
-    stringstream *inbuf = (stringstream *)input;
-    in = CorpusIO::createIO(*inbuf, config->getInputFormat(), *config, false, util);
-    strstream *outbuf = (strstream *)output;
-    out = CorpusIO::createIO(*outbuf, config->getOutputFormat(), *config, true, util);
+    try {
+        IOGuard io;
+        stringstream *inbuf = (stringstream *)input;
+        io.in = CorpusIO::createIO(*inbuf, config->getInputFormat(), *config, false, util);
+        strstream *outbuf = (strstream *)output;
+        io.out = CorpusIO::createIO(*outbuf, output_format(config, format), *config, true, util);
+
+        run_kytea_with_io(kytea, io.in, io.out);
+    } CATCH_KYTEA(err, ERROR_KYTEA)
+}
 
-    run_kytea_with_io(kytea, in, out);
+extern "C" void run_kytea_file_str(void *void_kytea, const char *input, void *output, int format, Error *err) {
+    Kytea *kytea = (Kytea *)void_kytea;
 
-    delete in;
-    delete out;
+    StringUtil *util = kytea->getStringUtil(); 
+
+    KyteaConfig *config = kytea->getConfig();
+
+    try {
+        IOGuard io;
+        io.in = CorpusIO::createIO(input, config->getInputFormat(), *config, false, util);
+        strstream *outbuf = (strstream *)output;
+        io.out = CorpusIO::createIO(*outbuf, output_format(config, format), *config, true, util);
+
+        run_kytea_with_io(kytea, io.in, io.out);
+    } CATCH_KYTEA(err, ERROR_KYTEA)
+}
+
+extern "C" void run_kytea_str_file(void *void_kytea, void *input, const char *output, int format, Error *err) {
+    Kytea *kytea = (Kytea *)void_kytea;
+
+    StringUtil *util = kytea->getStringUtil(); 
+
+    KyteaConfig *config = kytea->getConfig();
+
+    try {
+        IOGuard io;
+        stringstream *inbuf = (stringstream *)input;
+        io.in = CorpusIO::createIO(*inbuf, config->getInputFormat(), *config, false, util);
+        io.out = CorpusIO::createIO(output, output_format(config, format), *config, true, util);
+
+        run_kytea_with_io(kytea, io.in, io.out);
+    } CATCH_KYTEA(err, ERROR_KYTEA)
 }
 
-extern "C" void run_kytea_file_str(void *void_kytea, const char *input, void *output) {
+extern "C" void run_kytea_file_file(void *void_kytea, const char *input, const char *output, int format, Error *err) {
     Kytea *kytea = (Kytea *)void_kytea;
 
     StringUtil *util = kytea->getStringUtil(); 
 
     KyteaConfig *config = kytea->getConfig();
 
-    CorpusIO *in, *out;
-    in = CorpusIO::createIO(input, config->getInputFormat(), *config, false, util);
-    strstream *outbuf = (strstream *)output;
-    out = CorpusIO::createIO(*outbuf, config->getOutputFormat(), *config, true, util);
+    try {
+        IOGuard io;
+        io.in = CorpusIO::createIO(input, config->getInputFormat(), *config, false, util);
+        io.out = CorpusIO::createIO(output, output_format(config, format), *config, true, util);
+
+        run_kytea_with_io(kytea, io.in, io.out);
+    } CATCH_KYTEA(err, ERROR_KYTEA)
+}
+
+typedef pair<string, double> RustTag;
+
+struct RustWord {
+    string surface;
+    // Candidates of each tag level, the best first.
+    vector< vector<RustTag> > tags;
+    size_t begin;
+    size_t end;
+};
+
+extern "C" typedef struct {
+    // Byte offset of the gap in the input, i.e. the beginning of the next character.
+    size_t offset;
+    // Margin of the word segmentation classifier; positive if the gap is a word boundary.
+    double confidence;
+} Boundary;
+
+struct RustWords {
+    vector<RustWord> words;
+    // Every gap between two characters of a line.
+    vector<Boundary> boundaries;
+};
+
+extern "C" typedef struct {
+    size_t begin;
+    size_t end;
+} Span;
+
+// `offset` is the byte offset of the beginning of the sentence in the input.
+void collect_sentence(Kytea *kytea, const KyteaSentence *sentence, size_t offset, RustWords *words) {
+    KyteaConfig *config = kytea->getConfig();
+    StringUtil *util = kytea->getStringUtil();
+
+    size_t begin = offset;
+    for(KyteaSentence::Words::const_iterator it = sentence->words.begin(); it != sentence->words.end(); it++) {
+        RustWord word;
+        word.surface = util->showString(it->surface);
+        word.begin = begin;
+        word.end = begin + word.surface.size();
+        begin = word.end;
+
+        for(int i = 0; i < config->getNumTags(); i++) {
+            vector<RustTag> candidates;
+            if(i < (int)it->tags.size())
+                for(unsigned j = 0; j < it->tags[i].size(); j++)
+                    candidates.push_back(RustTag(util->showString(it->tags[i][j].first), it->tags[i][j].second));
+            word.tags.push_back(candidates);
+        }
+
+        words->words.push_back(word);
+    }
+
+    size_t gap = offset;
+    for(unsigned i = 0; i < sentence->wsConfs.size(); i++) {
+        gap += util->showChar(sentence->surface[i]).size();
+        Boundary boundary;
+        boundary.offset = gap;
+        boundary.confidence = sentence->wsConfs[i];
+        words->boundaries.push_back(boundary);
+    }
+}
+
+void collect_words(Kytea *kytea, CorpusIO *in, RustWords *words) {
+    StringUtil *util = kytea->getStringUtil();
 
-    run_kytea_with_io(kytea, in, out);
+    // Byte offset of the beginning of the current line in the input.
+    size_t offset = 0;
 
-    delete in;
-    delete out;
+    KyteaSentence* next;
+    while((next = in->readSentence()) != 0) {
+        analyze_sentence(kytea, next);
+        collect_sentence(kytea, next, offset, words);
+
+        // The raw reader consumes one line (and its newline) per sentence.
+        offset += util->showString(next->surface).size() + 1;
+        delete next;
+    }
 }
 
-extern "C" void run_kytea_str_file(void *void_kytea, void *input, const char *output) {
+extern "C" void *run_kytea_str_words(void *void_kytea, void *input, Error *err) {
     Kytea *kytea = (Kytea *)void_kytea;
 
     StringUtil *util = kytea->getStringUtil(); 
 
     KyteaConfig *config = kytea->getConfig();
 
-    CorpusIO *in, *out;
-    stringstream *inbuf = (stringstream *)input;
-    in = CorpusIO::createIO(*inbuf, config->getInputFormat(), *config, false, util);
-    out = CorpusIO::createIO(output, config->getOutputFormat(), *config, true, util);
+    RustWords *words = new RustWords;
+
+    try {
+        IOGuard io;
+        stringstream *inbuf = (stringstream *)input;
+        io.in = CorpusIO::createIO(*inbuf, config->getInputFormat(), *config, false, util);
+        collect_words(kytea, io.in, words);
+    } CATCH_KYTEA(err, ERROR_KYTEA)
 
-    run_kytea_with_io(kytea, in, out);
+    if(err->code != ERROR_NONE) {
+        delete words;
+        return 0;
+    }
 
-    delete in;
-    delete out;
+    return (void *)words;
 }
 
-extern "C" void run_kytea_file_file(void *void_kytea, const char *input, const char *output) {
+extern "C" void *run_kytea_file_words(void *void_kytea, const char *input, Error *err) {
     Kytea *kytea = (Kytea *)void_kytea;
 
     StringUtil *util = kytea->getStringUtil(); 
 
     KyteaConfig *config = kytea->getConfig();
 
-    CorpusIO *in, *out;
-    in = CorpusIO::createIO(input, config->getInputFormat(), *config, false, util);
-    out = CorpusIO::createIO(output, config->getOutputFormat(), *config, true, util);
+    RustWords *words = new RustWords;
+
+    try {
+        IOGuard io;
+        io.in = CorpusIO::createIO(input, config->getInputFormat(), *config, false, util);
+        collect_words(kytea, io.in, words);
+    } CATCH_KYTEA(err, ERROR_KYTEA)
+
+    if(err->code != ERROR_NONE) {
+        delete words;
+        return 0;
+    }
 
-    run_kytea_with_io(kytea, in, out);
+    return (void *)words;
+}
 
-    delete in;
-    delete out;
+extern "C" void delete_words(void *void_words) {
+    RustWords *words = (RustWords *)void_words;
+    delete words;
+}
+
+extern "C" size_t words_len(void *void_words) {
+    RustWords *words = (RustWords *)void_words;
+    return words->words.size();
+}
+
+extern "C" Str words_surface(void *void_words, size_t i) {
+    RustWords *words = (RustWords *)void_words;
+    const string &surface = words->words[i].surface;
+    Str str;
+    str.ptr = surface.data();
+    str.size = surface.size();
+    return str;
+}
+
+extern "C" Span words_span(void *void_words, size_t i) {
+    RustWords *words = (RustWords *)void_words;
+    Span span;
+    span.begin = words->words[i].begin;
+    span.end = words->words[i].end;
+    return span;
+}
+
+extern "C" size_t words_num_tags(void *void_words, size_t i) {
+    RustWords *words = (RustWords *)void_words;
+    return words->words[i].tags.size();
+}
+
+extern "C" size_t words_num_candidates(void *void_words, size_t i, size_t lev) {
+    RustWords *words = (RustWords *)void_words;
+    return words->words[i].tags[lev].size();
+}
+
+extern "C" typedef struct {
+    Str tag;
+    double score;
+} Candidate;
+
+// The caller must check that the k-th candidate exists.
+extern "C" Candidate words_candidate(void *void_words, size_t i, size_t lev, size_t k) {
+    RustWords *words = (RustWords *)void_words;
+    const RustTag &tag = words->words[i].tags[lev][k];
+    Candidate candidate;
+    candidate.tag.ptr = tag.first.data();
+    candidate.tag.size = tag.first.size();
+    candidate.score = tag.second;
+    return candidate;
+}
+
+extern "C" size_t words_num_boundaries(void *void_words) {
+    RustWords *words = (RustWords *)void_words;
+    return words->boundaries.size();
+}
+
+extern "C" Boundary words_boundary(void *void_words, size_t i) {
+    RustWords *words = (RustWords *)void_words;
+    return words->boundaries[i];
+}
+
+extern "C" typedef struct {
+    // Byte offset of the gap in the line.
+    size_t offset;
+    // 1 for a forced boundary, -1 for a forced non-boundary.
+    int bound;
+} Constraint;
+
+// Same margin as KyTea gives to the annotated gaps of the partial annotation format.
+#define CONSTRAINT_CONF 100.0
+
+extern "C" void *run_kytea_constrained(void *void_kytea, const char *line, size_t len, const Constraint *constraints, size_t num_constraints, Error *err) {
+    Kytea *kytea = (Kytea *)void_kytea;
+
+    StringUtil *util = kytea->getStringUtil(); 
+
+    RustWords *words = new RustWords;
+
+    // An empty line has no words, nor gaps.
+    if(len == 0)
+        return (void *)words;
+
+    try {
+        KyteaConfig *config = kytea->getConfig();
+        KyteaString surface = util->mapString(string(line, len));
+        KyteaSentence sentence(surface, util->normalize(surface));
+
+        // Index and margin of each constrained gap; the constraints are sorted by byte offset.
+        vector<pair<unsigned, double> > forced;
+        size_t gap = 0;
+        size_t j = 0;
+        for(unsigned i = 0; i < sentence.wsConfs.size() && j < num_constraints; i++) {
+            gap += util->showChar(surface[i]).size();
+            while(j < num_constraints && constraints[j].offset < gap)
+                j++;
+            if(j < num_constraints && constraints[j].offset == gap)
+                forced.push_back(make_pair(i, constraints[j].bound * CONSTRAINT_CONF));
+        }
+
+        // calculateWS() predicts every gap, so the constrained ones are set again afterwards,
+        // before the words are tagged.
+        if(config->getDoWS())
+            kytea->calculateWS(sentence);
+        for(size_t k = 0; k < forced.size(); k++)
+            sentence.wsConfs[forced[k].first] = forced[k].second;
+        sentence.refreshWS(config->getConfidence());
+        if(config->getDoTags())
+            for(int i = 0; i < config->getNumTags(); i++)
+                if(config->getDoTag(i))
+                    kytea->calculateTags(sentence, i);
+
+        collect_sentence(kytea, &sentence, 0, words);
+    } CATCH_KYTEA(err, ERROR_KYTEA)
+
+    if(err->code != ERROR_NONE) {
+        delete words;
+        return 0;
+    }
+
+    return (void *)words;
+}
+
+extern "C" typedef struct {
+    const char *const *corpora;
+    // 0 for the full annotation, 1 for the partial annotation.
+    const int *corpus_formats;
+    size_t num_corpora;
+    const char *const *dictionaries;
+    size_t num_dictionaries;
+    // The options below are left to KyTea if negative (or 0 for model_format, NULL for encoding).
+    int char_window;
+    int char_n;
+    int type_window;
+    int type_n;
+    int dictionary_n;
+    int solver;
+    double epsilon;
+    double cost;
+    int bias;
+    char model_format;
+    // "utf8", "euc" or "sjis", as the -encode option of train-kytea.
+    const char *encoding;
+} TrainConfig;
+
+extern "C" void train_kytea(const char *model, const TrainConfig *rust_config, Error *err) {
+    try {
+        Kytea kytea;
+
+        KyteaConfig *config = kytea.getConfig();
+        config->setDebug(0);
+        config->setOnTraining(true);
+        config->setModelFile(model);
+        // Set first, since the corpora and the dictionaries are read in this encoding.
+        if(rust_config->encoding)
+            config->setEncoding(rust_config->encoding);
+
+        for(size_t i = 0; i < rust_config->num_corpora; i++)
+            config->addCorpus(rust_config->corpora[i], rust_config->corpus_formats[i] == 1 ? CORP_FORMAT_PART : CORP_FORMAT_FULL);
+        for(size_t i = 0; i < rust_config->num_dictionaries; i++)
+            config->addDictionary(rust_config->dictionaries[i]);
+
+        if(rust_config->char_window >= 0)
+            config->setCharWindow(rust_config->char_window);
+        if(rust_config->char_n >= 0)
+            config->setCharN(rust_config->char_n);
+        if(rust_config->type_window >= 0)
+            config->setTypeWindow(rust_config->type_window);
+        if(rust_config->type_n >= 0)
+            config->setTypeN(rust_config->type_n);
+        if(rust_config->dictionary_n >= 0)
+            config->setDictionaryN(rust_config->dictionary_n);
+        if(rust_config->solver >= 0)
+            config->setSolverType(rust_config->solver);
+        if(rust_config->epsilon >= 0)
+            config->setEpsilon(rust_config->epsilon);
+        if(rust_config->cost >= 0)
+            config->setCost(rust_config->cost);
+        if(rust_config->bias >= 0)
+            config->setBias(rust_config->bias);
+        if(rust_config->model_format)
+            config->setModelFormat(rust_config->model_format);
+
+        // Writes the model to the model file at the end.
+        kytea.trainAll();
+    } CATCH_KYTEA(err, ERROR_KYTEA)
+}
+
+// Returns 1 and sets the line (without the newline) if any, 0 at the end, or -1 on error.
+typedef int (*ReadLine)(void *ctx, Str *line);
+// Returns 0 on success or -1 on error.
+typedef int (*WriteBytes)(void *ctx, const char *ptr, size_t size);
+
+extern "C" void run_kytea_callback(void *void_kytea, ReadLine read_line, WriteBytes write_bytes, void *ctx, int format, Error *err) {
+    Kytea *kytea = (Kytea *)void_kytea;
+
+    StringUtil *util = kytea->getStringUtil(); 
+
+    KyteaConfig *config = kytea->getConfig();
+
+    try {
+        // Each sentence is written here and then passed to Rust.
+        stringstream outbuf;
+        IOGuard io;
+        io.out = CorpusIO::createIO(outbuf, output_format(config, format), *config, true, util);
+        prepare_output(kytea, io.out);
+
+        Str line;
+        int status;
+        while((status = read_line(ctx, &line)) == 1) {
+            KyteaString surface = util->mapString(string(line.ptr, line.size));
+            KyteaSentence sentence(surface, util->normalize(surface));
+            analyze_sentence(kytea, &sentence);
+            io.out->writeSentence(&sentence);
+
+            string out = outbuf.str();
+            outbuf.str("");
+            outbuf.clear();
+            if(write_bytes(ctx, out.data(), out.size()) != 0)
+                return;
+        }
+    } CATCH_KYTEA(err, ERROR_KYTEA)
+}
+
+extern "C" int kytea_num_tags(void *void_kytea) {
+    Kytea *kytea = (Kytea *)void_kytea;
+    return kytea->getConfig()->getNumTags();
+}
+
+extern "C" int kytea_do_ws(void *void_kytea) {
+    Kytea *kytea = (Kytea *)void_kytea;
+    return kytea->getConfig()->getDoWS();
+}
+
+extern "C" int kytea_do_tags(void *void_kytea) {
+    Kytea *kytea = (Kytea *)void_kytea;
+    return kytea->getConfig()->getDoTags();
+}
+
+extern "C" int kytea_do_tag(void *void_kytea, int lev) {
+    Kytea *kytea = (Kytea *)void_kytea;
+    return kytea->getConfig()->getDoTag(lev);
+}
+
+// "utf8", "euc" or "sjis"
+extern "C" const char *kytea_encoding(void *void_kytea) {
+    Kytea *kytea = (Kytea *)void_kytea;
+    return kytea->getStringUtil()->getEncodingString();
+}
+
+extern "C" size_t kytea_num_features(void *void_kytea) {
+    Kytea *kytea = (Kytea *)void_kytea;
+    KyteaModel *model = kytea->getWSModel();
+    return model ? model->getNumFeatures() : 0;
+}
+
+extern "C" size_t kytea_dictionary_size(void *void_kytea) {
+    Kytea *kytea = (Kytea *)void_kytea;
+    Dictionary<ModelTagEntry> *dict = kytea->getDictionary();
+    return dict ? dict->getWordMap().size() : 0;
+}
+
+typedef vector<string> RustStrings;
+
+// Every tag of the level `lev` that appears in the dictionary of the model, sorted.
+extern "C" void *kytea_tags(void *void_kytea, int lev) {
+    Kytea *kytea = (Kytea *)void_kytea;
+    StringUtil *util = kytea->getStringUtil();
+
+    set<string> tags;
+    Dictionary<ModelTagEntry> *dict = kytea->getDictionary();
+    if(dict) {
+        const Dictionary<ModelTagEntry>::WordMap &words = dict->getWordMap();
+        for(Dictionary<ModelTagEntry>::WordMap::const_iterator it = words.begin(); it != words.end(); it++) {
+            const ModelTagEntry *entry = it->second;
+            if(lev < (int)entry->tags.size())
+                for(unsigned i = 0; i < entry->tags[lev].size(); i++)
+                    tags.insert(util->showString(entry->tags[lev][i]));
+        }
+    }
+
+    return (void *)new RustStrings(tags.begin(), tags.end());
+}
+
+extern "C" void delete_strings(void *void_strings) {
+    RustStrings *strings = (RustStrings *)void_strings;
+    delete strings;
+}
+
+extern "C" size_t strings_len(void *void_strings) {
+    RustStrings *strings = (RustStrings *)void_strings;
+    return strings->size();
+}
+
+extern "C" Str strings_get(void *void_strings, size_t i) {
+    RustStrings *strings = (RustStrings *)void_strings;
+    const string &s = (*strings)[i];
+    Str str;
+    str.ptr = s.data();
+    str.size = s.size();
+    return str;
 }
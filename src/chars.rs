@@ -0,0 +1,25 @@
+//! Classes of characters as KyTea sees them.
+
+/// Character type, as `StringUtil::findType()` of KyTea.
+pub(crate) fn char_type(c: char) -> char {
+    match c {
+        '\u{3040}'..='\u{309f}' => 'H',
+        '\u{30a0}'..='\u{30ff}' | '\u{31f0}'..='\u{31ff}' | '\u{ff66}'..='\u{ff9f}' => 'K',
+        '\u{3005}'
+        | '\u{3400}'..='\u{4dbf}'
+        | '\u{4e00}'..='\u{9fff}'
+        | '\u{f900}'..='\u{faff}' => 'k',
+        '0'..='9' => 'D',
+        'a'..='z' | 'A'..='Z' => 'R',
+        _ => 'O',
+    }
+}
+
+/// Maps full-width ASCII to ASCII, as `StringUtil::normalize()` of KyTea.
+pub(crate) fn normalize(c: char) -> char {
+    match c {
+        '\u{ff01}'..='\u{ff5e}' => char::from_u32(c as u32 - 0xfee0).unwrap_or(c),
+        '\u{3000}' => ' ',
+        _ => c,
+    }
+}
@@ -0,0 +1,436 @@
+use std::ffi::OsString;
+use std::fmt;
+use std::path::PathBuf;
+use std::process::Command;
+
+use super::model_path;
+use crate::encoding::{EncodeError, Encoding};
+use crate::format::{InputFormat, OutputFormat};
+use crate::{DELIM_STR, ELEM_DELIM, ESCAPE, TAG_DELIM, WORD_DELIM};
+
+/// Options of the `kytea` binary, checked to produce an output readable by this crate.
+///
+/// Options left unset keep the defaults of KyTea, except `-wordbound`, which is always a tab
+/// as [`WordIterator`](crate::WordIterator) expects.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KyteaCommandBuilder {
+    program: OsString,
+    model: Option<PathBuf>,
+    do_ws: bool,
+    do_tags: bool,
+    do_tag: Vec<bool>,
+    tag_max: Option<u32>,
+    unk_beam: Option<u32>,
+    unk_tag: Option<String>,
+    default_tag: Option<String>,
+    input: Option<InputFormat>,
+    output: Option<OutputFormat>,
+    tag_bound: Option<String>,
+    elem_bound: Option<String>,
+}
+
+impl Default for KyteaCommandBuilder {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl KyteaCommandBuilder {
+    pub fn new() -> Self {
+        Self {
+            program: OsString::from("kytea"),
+            model: None,
+            do_ws: true,
+            do_tags: true,
+            do_tag: Vec::new(),
+            tag_max: None,
+            unk_beam: None,
+            unk_tag: None,
+            default_tag: None,
+            input: None,
+            output: None,
+            tag_bound: None,
+            elem_bound: None,
+        }
+    }
+
+    /// The binary to run, `kytea` in `PATH` by default.
+    pub fn program(mut self, program: impl Into<OsString>) -> Self {
+        self.program = program.into();
+        self
+    }
+
+    /// `-model`: the model to read, or the one found by
+    /// [`ModelResolver`](crate::ModelResolver) if unset.
+    ///
+    /// If no model is found either, `kytea` falls back to the default model it was built with.
+    pub fn model(mut self, model: impl Into<PathBuf>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    /// `-nows`: skips word segmentation when `false`.
+    pub fn do_ws(mut self, do_ws: bool) -> Self {
+        self.do_ws = do_ws;
+        self
+    }
+
+    /// `-notags`: skips tagging when `false`.
+    pub fn do_tags(mut self, do_tags: bool) -> Self {
+        self.do_tags = do_tags;
+        self
+    }
+
+    /// `-notag N`: skips the tag level `lev` when `false`.
+    ///
+    /// Unlike the `kytea` binary, `lev` is 0-indexed.
+    pub fn do_tag(mut self, lev: usize, do_tag: bool) -> Self {
+        if self.do_tag.len() <= lev {
+            self.do_tag.resize(lev + 1, true);
+        }
+        self.do_tag[lev] = do_tag;
+        self
+    }
+
+    /// `-tagmax`: the maximum number of tag candidates written by [`OutputFormat::Conf`], 0 for
+    /// no limit.
+    pub fn tag_max(mut self, tag_max: u32) -> Self {
+        self.tag_max = Some(tag_max);
+        self
+    }
+
+    /// `-unkbeam`: the beam width used to estimate the tags of unknown words, 0 for no beam.
+    pub fn unk_beam(mut self, unk_beam: u32) -> Self {
+        self.unk_beam = Some(unk_beam);
+        self
+    }
+
+    /// `-unktag`: a tag appended to the words that are not in the dictionary.
+    ///
+    /// Passed in the encoding of the model, as are the other tags.
+    pub fn unk_tag(mut self, unk_tag: impl Into<String>) -> Self {
+        self.unk_tag = Some(unk_tag.into());
+        self
+    }
+
+    /// `-deftag`: a tag given to the words that cannot be tagged at all.
+    pub fn default_tag(mut self, default_tag: impl Into<String>) -> Self {
+        self.default_tag = Some(default_tag.into());
+        self
+    }
+
+    /// `-in`: the format of the input.
+    pub fn input_format(mut self, format: InputFormat) -> Self {
+        self.input = Some(format);
+        self
+    }
+
+    /// `-out`: the format of the output.
+    pub fn output_format(mut self, format: OutputFormat) -> Self {
+        self.output = Some(format);
+        self
+    }
+
+    /// `-tagbound`: the delimiter of the tags, which must be
+    /// [`TAG_DELIM`](crate::TAG_DELIM) for the parser.
+    pub fn tag_bound(mut self, tag_bound: impl Into<String>) -> Self {
+        self.tag_bound = Some(tag_bound.into());
+        self
+    }
+
+    /// `-elembound`: the delimiter of the tag candidates, which must be
+    /// [`ELEM_DELIM`](crate::ELEM_DELIM) for the parser.
+    pub fn elem_bound(mut self, elem_bound: impl Into<String>) -> Self {
+        self.elem_bound = Some(elem_bound.into());
+        self
+    }
+
+    /// Checks the options and returns the command.
+    pub fn build(&self) -> Result<Command, OptionError> {
+        self.check()?;
+
+        let mut comm = Command::new(&self.program);
+        let model = model_path(self.model.as_deref());
+        // A model that cannot be read is reported by `kytea` itself.
+        let encoding = model
+            .as_ref()
+            .and_then(|model| Encoding::of_model(model).ok())
+            .unwrap_or(Encoding::Utf8);
+        if let Some(model) = model {
+            comm.arg("-model").arg(model);
+        }
+        if !self.do_ws {
+            comm.arg("-nows");
+        }
+        if !self.do_tags {
+            comm.arg("-notags");
+        }
+        for (lev, _) in self
+            .do_tag
+            .iter()
+            .enumerate()
+            .filter(|(_, &do_tag)| !do_tag)
+        {
+            // 1-indexed in `kytea`.
+            comm.arg("-notag").arg((lev + 1).to_string());
+        }
+        if let Some(tag_max) = self.tag_max {
+            comm.arg("-tagmax").arg(tag_max.to_string());
+        }
+        if let Some(unk_beam) = self.unk_beam {
+            comm.arg("-unkbeam").arg(unk_beam.to_string());
+        }
+        if let Some(unk_tag) = &self.unk_tag {
+            comm.arg("-unktag")
+                .arg(encode_arg("-unktag", unk_tag, encoding)?);
+        }
+        if let Some(default_tag) = &self.default_tag {
+            comm.arg("-deftag")
+                .arg(encode_arg("-deftag", default_tag, encoding)?);
+        }
+        if let Some(input) = self.input {
+            comm.arg("-in").arg(input.kytea_name());
+        }
+        if let Some(output) = self.output {
+            comm.arg("-out").arg(output.kytea_name());
+        }
+        comm.args(["-wordbound", DELIM_STR]);
+        if let Some(tag_bound) = &self.tag_bound {
+            comm.arg("-tagbound").arg(tag_bound);
+        }
+        if let Some(elem_bound) = &self.elem_bound {
+            comm.arg("-elembound").arg(elem_bound);
+        }
+        Ok(comm)
+    }
+
+    /// Rejects the options whose output cannot be parsed by this crate.
+    fn check(&self) -> Result<(), OptionError> {
+        let bounds = [
+            ("-tagbound", &self.tag_bound, TAG_DELIM),
+            ("-elembound", &self.elem_bound, ELEM_DELIM),
+        ];
+        for (option, value, expected) in bounds {
+            match value {
+                Some(value) if value.as_bytes() != [expected] => {
+                    return Err(OptionError::Delimiter {
+                        option,
+                        value: value.clone(),
+                        expected: char::from(expected),
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        if self.output == Some(OutputFormat::Eda) {
+            return Err(OptionError::Format(OutputFormat::Eda));
+        }
+
+        // KyTea writes these tags as is, so a delimiter in them would split the word.
+        let tags = [("-unktag", &self.unk_tag), ("-deftag", &self.default_tag)];
+        for (option, value) in tags {
+            if let Some(value) = value {
+                let special = [WORD_DELIM, TAG_DELIM, ELEM_DELIM, ESCAPE, b'\n'];
+                if value.bytes().any(|b| special.contains(&b)) {
+                    return Err(OptionError::Tag {
+                        option,
+                        value: value.clone(),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Encodes a tag for `kytea`, which reads its arguments in the encoding of the model.
+fn encode_arg(
+    option: &'static str,
+    tag: &str,
+    encoding: Encoding,
+) -> Result<OsString, OptionError> {
+    let encoded = encoding
+        .encode(tag)
+        .map_err(|error| OptionError::Encoding { option, error })?;
+    #[cfg(unix)]
+    let arg = {
+        use std::os::unix::ffi::OsStrExt;
+        std::ffi::OsStr::from_bytes(&encoded.bytes).to_os_string()
+    };
+    // Arguments are Unicode on the other platforms.
+    #[cfg(not(unix))]
+    let arg = OsString::from(tag);
+    Ok(arg)
+}
+
+/// An option of [`KyteaCommandBuilder`] whose output cannot be parsed by this crate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OptionError {
+    /// A delimiter other than the one the parser reads.
+    Delimiter {
+        option: &'static str,
+        value: String,
+        expected: char,
+    },
+    /// An output format that no parser reads.
+    Format(OutputFormat),
+    /// A tag containing a delimiter of the output.
+    Tag { option: &'static str, value: String },
+    /// A tag that the encoding of the model cannot represent.
+    Encoding {
+        option: &'static str,
+        error: EncodeError,
+    },
+}
+
+impl fmt::Display for OptionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Delimiter {
+                option,
+                value,
+                expected,
+            } => write!(
+                f,
+                "{} {:?} cannot be parsed; the parser expects {:?}",
+                option, value, expected
+            ),
+            Self::Format(format) => {
+                write!(f, "-out {} cannot be parsed", format.kytea_name())
+            }
+            Self::Tag { option, value } => write!(
+                f,
+                "{} {:?} contains a delimiter of the output",
+                option, value
+            ),
+            Self::Encoding { option, error } => write!(f, "{}: {}", option, error),
+        }
+    }
+}
+
+impl std::error::Error for OptionError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn args(builder: &KyteaCommandBuilder) -> Vec<String> {
+        builder
+            .build()
+            .unwrap()
+            .get_args()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn build_options() {
+        let builder = KyteaCommandBuilder::new()
+            .program("/opt/kytea/bin/kytea")
+            .model("model.bin")
+            .do_ws(false)
+            .do_tag(1, false)
+            .tag_max(3)
+            .unk_beam(0)
+            .unk_tag("***")
+            .default_tag("UNK")
+            .input_format(InputFormat::Full)
+            .output_format(OutputFormat::Conf)
+            .tag_bound("/")
+            .elem_bound("&");
+        assert_eq!(
+            builder.build().unwrap().get_program(),
+            "/opt/kytea/bin/kytea"
+        );
+        assert_eq!(
+            args(&builder),
+            [
+                "-model",
+                "model.bin",
+                "-nows",
+                "-notag",
+                "2",
+                "-tagmax",
+                "3",
+                "-unkbeam",
+                "0",
+                "-unktag",
+                "***",
+                "-deftag",
+                "UNK",
+                "-in",
+                "full",
+                "-out",
+                "conf",
+                "-wordbound",
+                "\t",
+                "-tagbound",
+                "/",
+                "-elembound",
+                "&",
+            ]
+        );
+
+        let builder = KyteaCommandBuilder::new().model("model.bin").do_tags(false);
+        assert_eq!(
+            args(&builder),
+            ["-model", "model.bin", "-notags", "-wordbound", "\t"]
+        );
+    }
+
+    #[test]
+    fn reject_unreadable_options() {
+        let err = KyteaCommandBuilder::new()
+            .tag_bound("|")
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err,
+            OptionError::Delimiter {
+                option: "-tagbound",
+                value: String::from("|"),
+                expected: '/',
+            }
+        );
+        assert!(KyteaCommandBuilder::new().elem_bound(";").build().is_err());
+        assert_eq!(
+            KyteaCommandBuilder::new()
+                .output_format(OutputFormat::Eda)
+                .build()
+                .unwrap_err(),
+            OptionError::Format(OutputFormat::Eda)
+        );
+        assert!(KyteaCommandBuilder::new().unk_tag("/未知").build().is_err());
+        assert!(KyteaCommandBuilder::new()
+            .default_tag("a\tb")
+            .build()
+            .is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn encode_tags() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let model = std::env::temp_dir().join(format!("kytea-euc-{}.txt", std::process::id()));
+        std::fs::write(&model, "KyTea 0.4.7 T euc\n").unwrap();
+        let builder = KyteaCommandBuilder::new().model(&model).unk_tag("未知");
+        let comm = builder.build();
+        let err = builder.default_tag("😀").build().unwrap_err();
+        std::fs::remove_file(&model).unwrap();
+
+        let comm = comm.unwrap();
+        let args = comm.get_args().collect::<Vec<_>>();
+        assert_eq!(args[2], "-unktag");
+        assert_eq!(args[3].as_bytes(), b"\xcc\xa4\xc3\xce");
+        match err {
+            OptionError::Encoding { option, error } => {
+                assert_eq!(option, "-deftag");
+                assert_eq!(error.c, '😀');
+            }
+            e => panic!("unexpected error: {}", e),
+        }
+    }
+}
@@ -0,0 +1,535 @@
+//! Tokenization by the `kytea` command.
+
+use std::ffi::{OsStr, OsString};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Error, ErrorKind, Result, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Command, ExitStatus, Output, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+use crate::resolver::find_model;
+use crate::word::{lines, read_words};
+use crate::{Encoding, Tokenize, Word};
+
+mod builder;
+pub use builder::{KyteaCommandBuilder, OptionError};
+
+pub use crate::format::{InputFormat, OutputFormat};
+
+/// Runs `kytea` on the UTF-8 text in `in_path` and writes the UTF-8 output to `out_path`.
+///
+/// The model is chosen as in [`kytea_command()`]. If its header says it is for EUC-JP or
+/// Shift_JIS, the text is transcoded on the way.
+///
+/// Fails with [`CmdError::Failed`] and what `kytea` printed to stderr unless it exits
+/// successfully, e.g. if the model cannot be read.
+pub fn run_cmd(
+    in_path: impl AsRef<Path>,
+    out_path: impl AsRef<Path>,
+    model: Option<&Path>,
+) -> std::result::Result<(), CmdError> {
+    let model = model_path(model);
+    let model = model.as_deref();
+    let encoding = model_encoding(model)?;
+    if encoding.is_utf8() {
+        let mut command = kytea_command(model);
+        command
+            .stdin(File::open(in_path)?)
+            .stdout(File::create(out_path)?);
+        return run(&mut command, None).map(|_| ());
+    }
+
+    let text = std::fs::read_to_string(in_path)?;
+    let input = encoding
+        .encode(&text)
+        .map_err(|e| Error::new(ErrorKind::InvalidData, e))?
+        .bytes
+        .into_owned();
+
+    let mut command = kytea_command(model);
+    command.stdout(Stdio::piped());
+    let output = run(&mut command, Some(input))?;
+
+    std::fs::write(out_path, encoding.decode(&output.stdout).as_bytes())?;
+    Ok(())
+}
+
+/// Runs `command` to the end with its stderr captured, writing `input` to its stdin if given.
+fn run(command: &mut Command, input: Option<Vec<u8>>) -> std::result::Result<Output, CmdError> {
+    if input.is_some() {
+        command.stdin(Stdio::piped());
+    }
+    let mut child = command
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| CmdError::spawn(e, command.get_program()))?;
+    let writer = input.map(|input| {
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        // Written from another thread so that KyTea does not block on a full stdout.
+        std::thread::spawn(move || stdin.write_all(&input))
+    });
+    let output = child.wait_with_output()?;
+    let written = writer.map(|writer| {
+        writer
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    });
+
+    // Checked first since a failed write is likely caused by the process exiting early.
+    if !output.status.success() {
+        return Err(CmdError::Failed {
+            status: output.status,
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        });
+    }
+    written.transpose()?;
+    Ok(output)
+}
+
+/// A failure of [`run_cmd()`] or [`KyteaProcess::new()`].
+#[derive(Debug)]
+pub enum CmdError {
+    /// The command was not found.
+    NotFound {
+        program: OsString,
+        /// `$PATH` when the command was searched, if set.
+        path: Option<OsString>,
+    },
+    /// The command exited unsuccessfully.
+    Failed { status: ExitStatus, stderr: String },
+    /// The header of the model could not be read, e.g. the model does not exist.
+    Model { path: PathBuf, error: Error },
+    /// Reading or writing the files or the pipes failed.
+    Io(Error),
+}
+
+impl CmdError {
+    fn spawn(e: Error, program: &OsStr) -> Self {
+        if e.kind() == ErrorKind::NotFound {
+            Self::NotFound {
+                program: program.to_os_string(),
+                path: std::env::var_os("PATH"),
+            }
+        } else {
+            Self::Io(e)
+        }
+    }
+}
+
+impl fmt::Display for CmdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound {
+                program,
+                path: Some(path),
+            } => write!(
+                f,
+                "command not found: {} (searched PATH={})",
+                program.to_string_lossy(),
+                path.to_string_lossy(),
+            ),
+            Self::NotFound {
+                program,
+                path: None,
+            } => write!(
+                f,
+                "command not found: {} (PATH is not set)",
+                program.to_string_lossy(),
+            ),
+            Self::Failed { status, stderr } if stderr.trim().is_empty() => {
+                write!(f, "kytea failed with {}", status)
+            }
+            Self::Failed { status, stderr } => {
+                write!(f, "kytea failed with {}: {}", status, stderr.trim())
+            }
+            Self::Model { path, error } => {
+                write!(f, "cannot read the model {}: {}", path.display(), error)
+            }
+            Self::Io(e) => write!(f, "I/O error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for CmdError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Model { error, .. } => Some(error),
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<Error> for CmdError {
+    fn from(e: Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// A `kytea` command reading `model`, or the model found by
+/// [`ModelResolver`](crate::ModelResolver) if `None`.
+///
+/// If no model is found either, `kytea` falls back to the default model it was built with.
+/// See [`KyteaCommandBuilder`] for the other options.
+pub fn kytea_command(model: Option<&Path>) -> Command {
+    let mut builder = KyteaCommandBuilder::new();
+    if let Some(model) = model {
+        builder = builder.model(model);
+    }
+    builder.build().expect("the default options are readable")
+}
+
+fn model_path(model: Option<&Path>) -> Option<PathBuf> {
+    model.map(Path::to_path_buf).or_else(|| find_model().ok())
+}
+
+fn model_encoding(model: Option<&Path>) -> std::result::Result<Encoding, CmdError> {
+    match model {
+        Some(model) => Encoding::of_model(model).map_err(|error| CmdError::Model {
+            path: model.to_path_buf(),
+            error,
+        }),
+        None => Ok(Encoding::Utf8),
+    }
+}
+
+/// How long [`KyteaProcess`] waits for `kytea` to exit on drop before killing it.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How long [`KyteaProcess`] waits for the output of a line by default.
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A `kytea` process kept alive across calls, so that the model is loaded only once.
+///
+/// Each line of the input is written to its stdin and exactly one line is read back, relying on
+/// `kytea` flushing its output after every sentence. If the process dies, it is restarted and
+/// the line is sent again once. If no line comes back within the [timeout](Self::timeout), the
+/// process is killed and [`ErrorKind::TimedOut`] is returned, so that a command which buffers
+/// its output or answers nothing cannot block forever. On drop, its stdin is closed so that it
+/// exits by itself.
+#[derive(Debug)]
+pub struct KyteaProcess {
+    command: Command,
+    encoding: Encoding,
+    timeout: Duration,
+    child: Option<Running>,
+}
+
+#[derive(Debug)]
+struct Running {
+    child: Child,
+    stdin: ChildStdin,
+    /// Lines of stdout, read by another thread so that reading can time out.
+    stdout: Receiver<Result<Vec<u8>>>,
+}
+
+impl KyteaProcess {
+    /// Starts [`kytea_command()`] with `model`, transcoding as [`run_cmd()`] does.
+    pub fn new(model: Option<&Path>) -> std::result::Result<Self, CmdError> {
+        let model = model_path(model);
+        let encoding = model_encoding(model.as_deref())?;
+        let command = kytea_command(model.as_deref());
+        let program = command.get_program().to_os_string();
+        Self::from_command(command, encoding).map_err(|e| CmdError::spawn(e, &program))
+    }
+
+    /// Starts `command`, which reads and writes text in `encoding` and must print one line per
+    /// line of input, words delimited by [`WORD_DELIM`](crate::WORD_DELIM).
+    pub fn from_command(mut command: Command, encoding: Encoding) -> Result<Self> {
+        command.stdin(Stdio::piped()).stdout(Stdio::piped());
+        let mut process = Self {
+            command,
+            encoding,
+            timeout: READ_TIMEOUT,
+            child: None,
+        };
+        process.start()?;
+        Ok(process)
+    }
+
+    /// How long to wait for the output of each line, 30 seconds by default.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Tokenizes `text` line by line and returns the output of each line terminated by a
+    /// newline, in the same format as [`run_cmd()`].
+    pub fn tokenize(&mut self, text: &str) -> Result<String> {
+        let mut output = String::with_capacity(text.len() * 2);
+        for (_, line) in lines(text) {
+            // Not sent since there is nothing to analyze.
+            if !line.is_empty() {
+                output.push_str(&self.tokenize_text_line(line)?);
+            }
+            output.push('\n');
+        }
+        Ok(output)
+    }
+
+    /// Tokenizes `text` into words read from the output of `kytea`; offsets are byte indices in
+    /// `text`.
+    pub fn tokenize_words(&mut self, text: &str) -> Result<Vec<Word>> {
+        let mut words = Vec::new();
+        for (offset, line) in lines(text) {
+            if !line.is_empty() {
+                let output = self.tokenize_text_line(line)?;
+                words.extend(read_words(&output, line, offset));
+            }
+        }
+        Ok(words)
+    }
+
+    /// Tokenizes a line without its newline and returns the output without its newline.
+    fn tokenize_text_line(&mut self, line: &str) -> Result<String> {
+        let mut input = self
+            .encoding
+            .encode(line)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))?
+            .bytes
+            .into_owned();
+        input.push(b'\n');
+        let output = self.tokenize_line(&input)?;
+        Ok(self.encoding.decode(&output).into_owned())
+    }
+
+    /// Sends a line, restarting the process once if it is dead.
+    fn tokenize_line(&mut self, input: &[u8]) -> Result<Vec<u8>> {
+        match self.round_trip(input) {
+            Ok(output) => Ok(output),
+            // Not retried since the line itself may be what the process hangs on.
+            Err(e) if e.kind() == ErrorKind::TimedOut => Err(e),
+            Err(_) => {
+                self.stop();
+                self.start()?;
+                self.round_trip(input)
+            }
+        }
+    }
+
+    fn round_trip(&mut self, input: &[u8]) -> Result<Vec<u8>> {
+        let timeout = self.timeout;
+        let running = match &mut self.child {
+            Some(running) => running,
+            None => self.start()?,
+        };
+        running.stdin.write_all(input)?;
+        running.stdin.flush()?;
+        let mut output = match running.stdout.recv_timeout(timeout) {
+            Ok(output) => output?,
+            Err(RecvTimeoutError::Timeout) => {
+                self.kill();
+                return Err(Error::new(
+                    ErrorKind::TimedOut,
+                    format!("kytea wrote no output in {:?}", timeout),
+                ));
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                return Err(Error::new(
+                    ErrorKind::UnexpectedEof,
+                    "kytea exited before writing the output",
+                ))
+            }
+        };
+        if output.last() == Some(&b'\n') {
+            output.pop();
+        }
+        Ok(output)
+    }
+
+    fn start(&mut self) -> Result<&mut Running> {
+        let mut child = self.command.spawn()?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let mut stdout = BufReader::new(child.stdout.take().expect("stdout is piped"));
+        let (sender, receiver) = mpsc::channel();
+        // Ends when the process closes its stdout, i.e. exits or is killed.
+        std::thread::spawn(move || loop {
+            let mut line = Vec::new();
+            match stdout.read_until(b'\n', &mut line) {
+                Ok(0) => return,
+                Ok(_) => {
+                    if sender.send(Ok(line)).is_err() {
+                        return;
+                    }
+                }
+                Err(e) => {
+                    let _ = sender.send(Err(e));
+                    return;
+                }
+            }
+        });
+        Ok(self.child.insert(Running {
+            child,
+            stdin,
+            stdout: receiver,
+        }))
+    }
+
+    /// Kills the process without waiting for it to exit by itself.
+    fn kill(&mut self) {
+        if let Some(mut running) = self.child.take() {
+            let _ = running.child.kill();
+            let _ = running.child.wait();
+        }
+    }
+
+    /// Closes the stdin of the process and waits for it to exit, killing it after
+    /// [`SHUTDOWN_TIMEOUT`].
+    fn stop(&mut self) {
+        let (mut child, stdin) = match self.child.take() {
+            Some(running) => (running.child, running.stdin),
+            None => return,
+        };
+        drop(stdin);
+        let deadline = Instant::now() + SHUTDOWN_TIMEOUT;
+        while Instant::now() < deadline {
+            match child.try_wait() {
+                Ok(None) => std::thread::sleep(Duration::from_millis(10)),
+                _ => return,
+            }
+        }
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+}
+
+impl Tokenize for KyteaProcess {
+    type Error = Error;
+
+    #[inline]
+    fn tokenize(&mut self, text: &str) -> Result<String> {
+        KyteaProcess::tokenize(self, text)
+    }
+
+    #[inline]
+    fn tokenize_words(&mut self, text: &str) -> Result<Vec<Word>> {
+        KyteaProcess::tokenize_words(self, text)
+    }
+}
+
+impl Drop for KyteaProcess {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(all(test, unix))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn run_not_found() {
+        let mut command = Command::new("kytea-tokenizer-missing-command");
+        let err = run(&mut command, None).unwrap_err();
+        assert!(matches!(err, CmdError::NotFound { .. }), "{:?}", err);
+        assert!(err.to_string().contains("kytea-tokenizer-missing-command"));
+        if let Some(path) = std::env::var_os("PATH") {
+            assert!(err.to_string().contains(&*path.to_string_lossy()));
+        }
+    }
+
+    #[test]
+    fn run_failed() {
+        let mut command = Command::new("sh");
+        command.args(["-c", "echo 'cannot open model' >&2; exit 3"]);
+        match run(&mut command, Some(b"text\n".to_vec())).unwrap_err() {
+            CmdError::Failed { status, stderr } => {
+                assert_eq!(status.code(), Some(3));
+                assert_eq!(stderr, "cannot open model\n");
+            }
+            err => panic!("unexpected error: {:?}", err),
+        }
+
+        let mut command = Command::new("cat");
+        command.stdout(Stdio::piped());
+        let output = run(&mut command, Some(b"text\n".to_vec())).unwrap();
+        assert_eq!(output.stdout, b"text\n");
+    }
+
+    #[test]
+    fn process_round_trip() {
+        let mut process = KyteaProcess::from_command(Command::new("cat"), Encoding::Utf8).unwrap();
+        assert_eq!(process.tokenize("a\tb\n\nc").unwrap(), "a\tb\n\nc\n");
+        assert_eq!(process.tokenize("").unwrap(), "");
+    }
+
+    #[test]
+    fn process_restarts() {
+        // Answers a single line and exits.
+        let mut command = Command::new("sh");
+        command.args(["-c", "read line; echo \"<$line>\""]);
+        let mut process = KyteaProcess::from_command(command, Encoding::Utf8).unwrap();
+        assert_eq!(process.tokenize("a\nb\n").unwrap(), "<a>\n<b>\n");
+    }
+
+    #[test]
+    fn process_transcodes() {
+        let mut process = KyteaProcess::from_command(Command::new("cat"), Encoding::EucJp).unwrap();
+        assert_eq!(process.tokenize("東京").unwrap(), "東京\n");
+    }
+
+    #[test]
+    fn process_words() {
+        // Splits words at spaces, as `kytea` would with tabs.
+        let mut command = Command::new("sed");
+        command.args(["-u", "s/ /\t/g"]);
+        let mut process = KyteaProcess::from_command(command, Encoding::Utf8).unwrap();
+        let words = Tokenize::tokenize_words(
+            &mut process,
+            "東京 に
+
+a/b",
+        )
+        .unwrap();
+        let words = words
+            .iter()
+            .map(|w| (w.surface.as_str(), &*w.tags, w.offset_from, w.offset_to))
+            .collect::<Vec<_>>();
+        let no_tags: &[String] = &[];
+        assert_eq!(
+            words,
+            [
+                ("東京", no_tags, 0, 6),
+                ("に", no_tags, 7, 10),
+                ("a", &[String::from("b")][..], 12, 13),
+            ]
+        );
+    }
+
+    #[test]
+    fn process_times_out() {
+        // Reads lines but never answers.
+        let mut command = Command::new("sh");
+        command.args(["-c", "while read line; do :; done"]);
+        let mut process = KyteaProcess::from_command(command, Encoding::Utf8)
+            .unwrap()
+            .timeout(Duration::from_millis(100));
+        let start = Instant::now();
+        let err = process.tokenize("a").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::TimedOut);
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(process.child.is_none());
+    }
+
+    #[test]
+    fn missing_model() {
+        let dir = std::env::temp_dir().join(format!("kytea-missing-{}", std::process::id()));
+        let model = dir.join("model.bin");
+        let input = dir.join("input.txt");
+
+        let err = run_cmd(&input, dir.join("output.txt"), Some(&model)).unwrap_err();
+        match &err {
+            CmdError::Model { path, error } => {
+                assert_eq!(path, &model);
+                assert_eq!(error.kind(), ErrorKind::NotFound);
+            }
+            err => panic!("unexpected error: {:?}", err),
+        }
+        assert!(err.to_string().contains(&*model.to_string_lossy()));
+
+        let err = KyteaProcess::new(Some(&model)).unwrap_err();
+        assert!(matches!(err, CmdError::Model { path, .. } if path == model));
+    }
+}
@@ -0,0 +1,183 @@
+//! Writer of KyTea dictionary files.
+//!
+//! A dictionary has one word per line in the full annotation format, e.g. `東京/名詞/とうきょう`.
+
+#[cfg(any(feature = "ffi", feature = "cmd"))]
+use crate::Encoding;
+use crate::{ELEM_DELIM, ESCAPE, TAG_DELIM};
+
+use std::io::{Result, Write};
+
+/// Word delimiter of the files read by KyTea in the full annotation format.
+pub(crate) const FULL_WORD_DELIM: u8 = b' ';
+
+/// Bytes escaped in the full annotation format.
+pub(crate) const FULL_SPECIAL: &[u8] = &[FULL_WORD_DELIM, TAG_DELIM, ELEM_DELIM, ESCAPE];
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct DictionaryEntry {
+    pub surface: String,
+    /// Tags of each tag level, e.g. the PoS and the pronunciation.
+    pub tags: Vec<String>,
+}
+
+impl DictionaryEntry {
+    pub fn new(
+        surface: impl Into<String>,
+        tags: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        Self {
+            surface: surface.into(),
+            tags: tags.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+pub struct DictionaryWriter<W> {
+    inner: W,
+    #[cfg(any(feature = "ffi", feature = "cmd"))]
+    encoding: Encoding,
+}
+
+impl<W: Write> DictionaryWriter<W> {
+    /// Writes a dictionary in UTF-8.
+    #[inline]
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            #[cfg(any(feature = "ffi", feature = "cmd"))]
+            encoding: Encoding::Utf8,
+        }
+    }
+
+    /// Writes a dictionary for a model in `encoding`, which KyTea reads without transcoding.
+    ///
+    /// An entry that `encoding` cannot represent is rejected with
+    /// [`ErrorKind::InvalidInput`](std::io::ErrorKind::InvalidInput).
+    #[cfg(any(feature = "ffi", feature = "cmd"))]
+    #[inline]
+    pub fn with_encoding(inner: W, encoding: Encoding) -> Self {
+        Self { inner, encoding }
+    }
+
+    /// Writes an entry; tags after an empty one are dropped since KyTea cannot skip a tag level.
+    pub fn write_entry(&mut self, entry: &DictionaryEntry) -> Result<()> {
+        let mut line = Vec::new();
+        write_word(&mut line, &entry.surface, &entry.tags)?;
+        line.push(b'\n');
+        #[cfg(any(feature = "ffi", feature = "cmd"))]
+        if !self.encoding.is_utf8() {
+            // `write_word()` writes UTF-8.
+            let line = String::from_utf8(line).expect("entries are UTF-8");
+            return self.inner.write_all(&self.encoding.encode(&line)?.bytes);
+        }
+        self.inner.write_all(&line)
+    }
+
+    pub fn write_entries<'a>(
+        &mut self,
+        entries: impl IntoIterator<Item = &'a DictionaryEntry>,
+    ) -> Result<()> {
+        for entry in entries {
+            self.write_entry(entry)?;
+        }
+        Ok(())
+    }
+
+    #[inline]
+    pub fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+/// Writes the entries to a new dictionary file at `path`.
+pub fn write_dictionary<'a>(
+    path: impl AsRef<std::path::Path>,
+    entries: impl IntoIterator<Item = &'a DictionaryEntry>,
+) -> Result<()> {
+    let file = std::fs::File::create(path)?;
+    let mut writer = DictionaryWriter::new(std::io::BufWriter::new(file));
+    writer.write_entries(entries)?;
+    writer.flush()
+}
+
+/// Writes the entries to a new dictionary file at `path` for a model in `encoding`.
+#[cfg(any(feature = "ffi", feature = "cmd"))]
+pub fn write_dictionary_with_encoding<'a>(
+    path: impl AsRef<std::path::Path>,
+    entries: impl IntoIterator<Item = &'a DictionaryEntry>,
+    encoding: Encoding,
+) -> Result<()> {
+    let file = std::fs::File::create(path)?;
+    let mut writer = DictionaryWriter::with_encoding(std::io::BufWriter::new(file), encoding);
+    writer.write_entries(entries)?;
+    writer.flush()
+}
+
+pub(crate) fn write_escaped(w: &mut impl Write, s: &str, special: &[u8]) -> Result<()> {
+    for c in s.chars() {
+        if c.is_ascii() && special.contains(&(c as u8)) {
+            w.write_all(&[ESCAPE])?;
+        }
+        write!(w, "{}", c)?;
+    }
+    Ok(())
+}
+
+/// Writes `surface/tag1/tag2...` in the full annotation format.
+pub(crate) fn write_word(
+    w: &mut impl Write,
+    surface: &str,
+    tags: &[impl AsRef<str>],
+) -> Result<()> {
+    write_escaped(w, surface, FULL_SPECIAL)?;
+    for tag in tags
+        .iter()
+        .map(AsRef::as_ref)
+        .take_while(|tag| !tag.is_empty())
+    {
+        w.write_all(&[TAG_DELIM])?;
+        write_escaped(w, tag, FULL_SPECIAL)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn dictionary_writer() {
+        let entries = [
+            DictionaryEntry::new("東京", ["名詞", "とうきょう"]),
+            DictionaryEntry::new("a b/c", ["名詞", "", "x"]),
+            DictionaryEntry::new("\\&", Vec::<String>::new()),
+        ];
+        let mut writer = DictionaryWriter::new(Vec::new());
+        writer.write_entries(&entries).unwrap();
+        let dict = String::from_utf8(writer.into_inner()).unwrap();
+        assert_eq!(dict, "東京/名詞/とうきょう\na\\ b\\/c/名詞\n\\\\\\&\n");
+    }
+
+    #[cfg(any(feature = "ffi", feature = "cmd"))]
+    #[test]
+    fn encoded_dictionary_writer() {
+        let mut writer = DictionaryWriter::with_encoding(Vec::new(), Encoding::EucJp);
+        writer
+            .write_entry(&DictionaryEntry::new("東京", ["名詞"]))
+            .unwrap();
+        assert_eq!(writer.into_inner(), b"\xc5\xec\xb5\xfe/\xcc\xbe\xbb\xec\n");
+
+        let mut writer = DictionaryWriter::with_encoding(Vec::new(), Encoding::ShiftJis);
+        let e = writer
+            .write_entry(&DictionaryEntry::new("東京", ["😀"]))
+            .unwrap_err();
+        assert_eq!(e.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(writer.into_inner().is_empty());
+    }
+}
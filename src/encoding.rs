@@ -0,0 +1,256 @@
+//! Character encodings of KyTea models.
+//!
+//! A model built for EUC-JP or Shift_JIS reads and writes text in that encoding, while this crate
+//! always takes and returns UTF-8; the text is transcoded on the way.
+
+use std::borrow::Cow;
+use std::fmt;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+#[cfg(feature = "json")]
+use serde::{Deserialize, Serialize};
+
+/// Character encoding of a KyTea model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub enum Encoding {
+    Utf8,
+    EucJp,
+    ShiftJis,
+    /// Treated as UTF-8.
+    Unknown,
+}
+
+impl Encoding {
+    /// Parses the name used by KyTea (`-encode` of `train-kytea`).
+    pub fn from_kytea_name(name: &str) -> Self {
+        match name {
+            "utf8" => Self::Utf8,
+            "euc" => Self::EucJp,
+            "sjis" => Self::ShiftJis,
+            _ => Self::Unknown,
+        }
+    }
+
+    /// The name used by KyTea; an unknown encoding is UTF-8.
+    pub fn kytea_name(self) -> &'static str {
+        match self {
+            Self::Utf8 | Self::Unknown => "utf8",
+            Self::EucJp => "euc",
+            Self::ShiftJis => "sjis",
+        }
+    }
+
+    /// Reads the encoding from the header of a model file, e.g. `KyTea 0.4.7 B utf8`.
+    pub fn of_model(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let mut header = Vec::new();
+        BufReader::new(std::fs::File::open(path)?).read_until(b'\n', &mut header)?;
+        let header = String::from_utf8_lossy(&header);
+        let mut fields = header.split_whitespace();
+        if fields.next() != Some("KyTea") {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "not a KyTea model",
+            ));
+        }
+        Ok(fields
+            .nth(2)
+            .map(Self::from_kytea_name)
+            .unwrap_or(Self::Unknown))
+    }
+
+    /// Whether text is passed to KyTea as is.
+    #[inline]
+    pub fn is_utf8(self) -> bool {
+        self.transcoder().is_none()
+    }
+
+    fn transcoder(self) -> Option<&'static encoding_rs::Encoding> {
+        match self {
+            Self::EucJp => Some(encoding_rs::EUC_JP),
+            Self::ShiftJis => Some(encoding_rs::SHIFT_JIS),
+            Self::Utf8 | Self::Unknown => None,
+        }
+    }
+
+    /// Decodes the output of KyTea; malformed sequences are replaced with U+FFFD.
+    pub(crate) fn decode(self, bytes: &[u8]) -> Cow<'_, str> {
+        match self.transcoder() {
+            Some(transcoder) => transcoder.decode_without_bom_handling(bytes).0,
+            None => String::from_utf8_lossy(bytes),
+        }
+    }
+
+    /// Encodes `text` for KyTea, failing on a character the encoding cannot represent.
+    #[cfg(any(feature = "ffi", feature = "cmd"))]
+    pub(crate) fn encode(self, text: &str) -> Result<Encoded<'_>, EncodeError> {
+        let transcoder = match self.transcoder() {
+            Some(transcoder) => transcoder,
+            None => {
+                return Ok(Encoded {
+                    bytes: Cow::Borrowed(text.as_bytes()),
+                    #[cfg(feature = "ffi")]
+                    offsets: Vec::new(),
+                })
+            }
+        };
+
+        let mut encoder = transcoder.new_encoder();
+        let mut bytes = Vec::with_capacity(text.len());
+        let mut offsets = Vec::with_capacity(text.len() + 1);
+        let mut buf = [0u8; 8];
+        for (offset, c) in text.char_indices() {
+            offsets.push((bytes.len(), offset));
+            let (result, _, written) = encoder.encode_from_utf8_without_replacement(
+                &text[offset..offset + c.len_utf8()],
+                &mut buf,
+                false,
+            );
+            if result != encoding_rs::EncoderResult::InputEmpty {
+                return Err(EncodeError {
+                    encoding: self,
+                    c,
+                    offset,
+                });
+            }
+            bytes.extend_from_slice(&buf[..written]);
+        }
+        offsets.push((bytes.len(), text.len()));
+        Ok(Encoded {
+            bytes: Cow::Owned(bytes),
+            #[cfg(feature = "ffi")]
+            offsets,
+        })
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Utf8 => "UTF-8",
+            Self::EucJp => "EUC-JP",
+            Self::ShiftJis => "Shift_JIS",
+            Self::Unknown => "an unknown encoding",
+        })
+    }
+}
+
+/// Text encoded for a model, which maps byte offsets back to the original UTF-8 text.
+#[cfg(any(feature = "ffi", feature = "cmd"))]
+pub(crate) struct Encoded<'a> {
+    pub(crate) bytes: Cow<'a, [u8]>,
+    /// `(offset in bytes, offset in the text)` of each character and of the end, sorted by both;
+    /// empty if the text is not transcoded.
+    #[cfg(feature = "ffi")]
+    offsets: Vec<(usize, usize)>,
+}
+
+// Offsets are reported only by `ffi`.
+#[cfg(feature = "ffi")]
+impl Encoded<'_> {
+    /// Maps an offset in [`Encoded::bytes`] to the offset in the text.
+    pub(crate) fn to_text_offset(&self, offset: usize) -> usize {
+        self.map(offset, |&(bytes, _)| bytes, |&(_, text)| text)
+    }
+
+    /// Maps an offset in the text to the offset in [`Encoded::bytes`].
+    pub(crate) fn to_bytes_offset(&self, offset: usize) -> usize {
+        self.map(offset, |&(_, text)| text, |&(bytes, _)| bytes)
+    }
+
+    fn map(
+        &self,
+        offset: usize,
+        from: impl Fn(&(usize, usize)) -> usize,
+        to: impl Fn(&(usize, usize)) -> usize,
+    ) -> usize {
+        if self.offsets.is_empty() {
+            return offset;
+        }
+        // An offset inside a character is mapped to the beginning of the character.
+        let i = match self.offsets.binary_search_by_key(&offset, from) {
+            Ok(i) => i,
+            Err(i) => i.saturating_sub(1),
+        };
+        to(&self.offsets[i])
+    }
+}
+
+/// A character the encoding of the model cannot represent.
+#[cfg(any(feature = "ffi", feature = "cmd"))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncodeError {
+    pub encoding: Encoding,
+    pub c: char,
+    /// Offset (byte index) of the character in the text.
+    pub offset: usize,
+}
+
+#[cfg(any(feature = "ffi", feature = "cmd"))]
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?} at {} cannot be encoded in {}",
+            self.c, self.offset, self.encoding
+        )
+    }
+}
+
+#[cfg(any(feature = "ffi", feature = "cmd"))]
+impl std::error::Error for EncodeError {}
+
+/// For the writers of KyTea files, which report [`std::io::ErrorKind::InvalidInput`].
+#[cfg(any(feature = "ffi", feature = "cmd"))]
+impl From<EncodeError> for std::io::Error {
+    fn from(e: EncodeError) -> Self {
+        Self::new(std::io::ErrorKind::InvalidInput, e)
+    }
+}
+
+#[cfg(all(test, any(feature = "ffi", feature = "cmd")))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn encode_euc_jp() {
+        let text = "東京a\n語";
+        let encoded = Encoding::EucJp.encode(text).unwrap();
+        assert_eq!(&*encoded.bytes, b"\xc5\xec\xb5\xfea\n\xb8\xec".as_slice());
+        assert_eq!(Encoding::EucJp.decode(&encoded.bytes), text);
+    }
+
+    #[cfg(feature = "ffi")]
+    #[test]
+    fn map_offsets() {
+        let text = "東京a\n語";
+        let encoded = Encoding::EucJp.encode(text).unwrap();
+        assert_eq!(encoded.to_text_offset(2), 3);
+        assert_eq!(encoded.to_text_offset(5), 7);
+        assert_eq!(encoded.to_text_offset(8), text.len());
+        assert_eq!(encoded.to_bytes_offset(6), 4);
+
+        let encoded = Encoding::Utf8.encode("東京").unwrap();
+        assert_eq!(encoded.to_text_offset(3), 3);
+    }
+
+    #[test]
+    fn encode_unmappable() {
+        let err = Encoding::ShiftJis.encode("a😀").err().unwrap();
+        assert_eq!((err.c, err.offset), ('😀', 1));
+    }
+
+    #[test]
+    fn kytea_names() {
+        for encoding in [Encoding::Utf8, Encoding::EucJp, Encoding::ShiftJis] {
+            assert_eq!(Encoding::from_kytea_name(encoding.kytea_name()), encoding);
+        }
+    }
+
+    #[test]
+    fn encode_utf8() {
+        let encoded = Encoding::Utf8.encode("東京").unwrap();
+        assert!(matches!(encoded.bytes, Cow::Borrowed(_)));
+    }
+}
@@ -0,0 +1,217 @@
+use libc::c_char;
+use libc::c_int;
+use libc::size_t;
+
+use std::ffi::CString;
+use std::path::{Path, PathBuf};
+
+use super::{path_to_cstring, Encoding, ModelError};
+
+/// Analysis options applied to the [`Model`](super::Model) when it is loaded.
+///
+/// They mirror the options of the `kytea` binary; an option left unset keeps the value of the
+/// model.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelConfig {
+    do_ws: bool,
+    do_tags: bool,
+    do_tag: Vec<bool>,
+    unk_tag: Option<String>,
+    tag_max: Option<u32>,
+    unk_beam: Option<u32>,
+    default_tag: Option<String>,
+    dictionaries: Vec<PathBuf>,
+}
+
+impl Default for ModelConfig {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ModelConfig {
+    pub fn new() -> Self {
+        Self {
+            do_ws: true,
+            do_tags: true,
+            do_tag: Vec::new(),
+            unk_tag: None,
+            tag_max: None,
+            unk_beam: None,
+            default_tag: None,
+            dictionaries: Vec::new(),
+        }
+    }
+
+    /// `-nows`: skips word segmentation when `false`.
+    pub fn do_ws(mut self, do_ws: bool) -> Self {
+        self.do_ws = do_ws;
+        self
+    }
+
+    /// `-notags`: skips tagging when `false`.
+    pub fn do_tags(mut self, do_tags: bool) -> Self {
+        self.do_tags = do_tags;
+        self
+    }
+
+    /// `-notag N`: skips the tag level `lev` when `false`.
+    ///
+    /// Unlike the `kytea` binary, `lev` is 0-indexed.
+    pub fn do_tag(mut self, lev: usize, do_tag: bool) -> Self {
+        if self.do_tag.len() <= lev {
+            self.do_tag.resize(lev + 1, true);
+        }
+        self.do_tag[lev] = do_tag;
+        self
+    }
+
+    /// `-unktag`: a tag appended to the words that are not in the dictionary.
+    ///
+    /// The tag is encoded in the encoding of the model when it is loaded.
+    pub fn unk_tag(mut self, unk_tag: impl Into<String>) -> Self {
+        self.unk_tag = Some(unk_tag.into());
+        self
+    }
+
+    /// `-tagmax`: the maximum number of tag candidates kept for each tag level, 0 for no limit.
+    pub fn tag_max(mut self, tag_max: u32) -> Self {
+        self.tag_max = Some(tag_max);
+        self
+    }
+
+    /// `-unkbeam`: the beam width used to estimate the tags of unknown words, 0 for no beam.
+    pub fn unk_beam(mut self, unk_beam: u32) -> Self {
+        self.unk_beam = Some(unk_beam);
+        self
+    }
+
+    /// `-deftag`: a tag given to the words that cannot be tagged at all.
+    ///
+    /// The tag is encoded in the encoding of the model when it is loaded.
+    pub fn default_tag(mut self, default_tag: impl Into<String>) -> Self {
+        self.default_tag = Some(default_tag.into());
+        self
+    }
+
+    /// A user dictionary whose words are added to the dictionary of the model.
+    ///
+    /// See [`crate::dictionary`] for the format and a writer of it. The file is read in the
+    /// encoding of the model, so write it with
+    /// [`DictionaryWriter::with_encoding()`](crate::dictionary::DictionaryWriter::with_encoding)
+    /// for a model not in UTF-8.
+    pub fn dictionary(mut self, path: impl AsRef<Path>) -> Self {
+        self.dictionaries.push(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Calls `f` with the raw config, which borrows `self`, for a model in `encoding`.
+    pub(super) fn with_raw<R>(
+        &self,
+        encoding: Encoding,
+        f: impl FnOnce(&RawConfig) -> R,
+    ) -> Result<R, ModelError> {
+        let unk_tag = encode_tag(&self.unk_tag, encoding)?;
+        let default_tag = encode_tag(&self.default_tag, encoding)?;
+        let dictionaries = self
+            .dictionaries
+            .iter()
+            .map(|d| {
+                // Checked here since a file that cannot be read is not reported by KyTea.
+                if d.is_file() {
+                    path_to_cstring(d)
+                } else {
+                    Err(ModelError::NotFound(format!(
+                        "the dictionary {} does not exist",
+                        d.display()
+                    )))
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let dictionaries = dictionaries.iter().map(|d| d.as_ptr()).collect::<Vec<_>>();
+        let raw = RawConfig {
+            do_ws: self.do_ws as c_int,
+            do_tags: self.do_tags as c_int,
+            do_tag: self.do_tag.as_ptr() as *const u8,
+            num_do_tag: self.do_tag.len(),
+            unk_tag: as_ptr_or_null(&unk_tag),
+            tag_max: self.tag_max.map(|n| n as c_int).unwrap_or(-1),
+            unk_beam: self.unk_beam.map(|n| n as c_int).unwrap_or(-1),
+            default_tag: as_ptr_or_null(&default_tag),
+            dictionaries: dictionaries.as_ptr(),
+            num_dictionaries: dictionaries.len(),
+        };
+        Ok(f(&raw))
+    }
+}
+
+fn encode_tag(tag: &Option<String>, encoding: Encoding) -> Result<Option<CString>, ModelError> {
+    match tag {
+        Some(tag) => {
+            let encoded = encoding.encode(tag)?;
+            CString::new(encoded.bytes.into_owned())
+                .map(Some)
+                .map_err(|_| {
+                    ModelError::InvalidInput(format!("the tag {:?} contains a nul byte", tag))
+                })
+        }
+        None => Ok(None),
+    }
+}
+
+fn as_ptr_or_null(s: &Option<CString>) -> *const c_char {
+    s.as_ref().map(|s| s.as_ptr()).unwrap_or(std::ptr::null())
+}
+
+#[repr(C)]
+pub(super) struct RawConfig {
+    do_ws: c_int,
+    do_tags: c_int,
+    do_tag: *const u8,
+    num_do_tag: size_t,
+    unk_tag: *const c_char,
+    tag_max: c_int,
+    unk_beam: c_int,
+    default_tag: *const c_char,
+    dictionaries: *const *const c_char,
+    num_dictionaries: size_t,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::ffi::CStr;
+
+    fn raw_tag(tag: *const c_char) -> Option<Vec<u8>> {
+        (!tag.is_null()).then(|| unsafe { CStr::from_ptr(tag) }.to_bytes().to_vec())
+    }
+
+    #[test]
+    fn encode_tags() {
+        let config = ModelConfig::new().unk_tag("未知").default_tag("UNK");
+        let tags = config
+            .with_raw(Encoding::EucJp, |raw| {
+                (raw_tag(raw.unk_tag), raw_tag(raw.default_tag))
+            })
+            .unwrap();
+        assert_eq!(tags.0.as_deref(), Some(&b"\xcc\xa4\xc3\xce"[..]));
+        assert_eq!(tags.1.as_deref(), Some(&b"UNK"[..]));
+
+        let tags = ModelConfig::new()
+            .with_raw(Encoding::Utf8, |raw| raw_tag(raw.unk_tag))
+            .unwrap();
+        assert_eq!(tags, None);
+    }
+
+    #[test]
+    fn reject_unencodable_tags() {
+        let config = ModelConfig::new().unk_tag("😀");
+        assert!(matches!(
+            config.with_raw(Encoding::ShiftJis, |_| ()),
+            Err(ModelError::InvalidInput(_))
+        ));
+        let config = ModelConfig::new().default_tag("a\0b");
+        assert!(config.with_raw(Encoding::Utf8, |_| ()).is_err());
+    }
+}
@@ -0,0 +1,119 @@
+use libc::c_char;
+use libc::c_int;
+
+use std::ffi::CStr;
+use std::fmt;
+
+use crate::encoding::EncodeError;
+use crate::resolver::ModelNotFound;
+
+/// An error caught in the C++ shim and translated from the exception thrown by KyTea.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModelError {
+    /// The model (or an input/output file) does not exist.
+    NotFound(String),
+    /// The model file exists but KyTea could not parse it.
+    BadFormat(String),
+    /// The model was written by an incompatible version of KyTea.
+    UnsupportedVersion(String),
+    /// Reading or writing a stream failed.
+    Io(String),
+    /// Any other failure reported by KyTea during the analysis.
+    Kytea(String),
+    /// The input was rejected before being passed to KyTea.
+    InvalidInput(String),
+}
+
+impl ModelError {
+    pub fn message(&self) -> &str {
+        match self {
+            Self::NotFound(msg)
+            | Self::BadFormat(msg)
+            | Self::UnsupportedVersion(msg)
+            | Self::Io(msg)
+            | Self::Kytea(msg)
+            | Self::InvalidInput(msg) => msg,
+        }
+    }
+}
+
+impl fmt::Display for ModelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound(msg) => write!(f, "not found: {}", msg),
+            Self::BadFormat(msg) => write!(f, "bad model format: {}", msg),
+            Self::UnsupportedVersion(msg) => write!(f, "unsupported model version: {}", msg),
+            Self::Io(msg) => write!(f, "I/O error: {}", msg),
+            Self::Kytea(msg) => write!(f, "KyTea error: {}", msg),
+            Self::InvalidInput(msg) => write!(f, "invalid input: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for ModelError {}
+
+impl From<ModelNotFound> for ModelError {
+    fn from(e: ModelNotFound) -> Self {
+        Self::NotFound(e.to_string())
+    }
+}
+
+impl From<EncodeError> for ModelError {
+    fn from(e: EncodeError) -> Self {
+        Self::InvalidInput(e.to_string())
+    }
+}
+
+const ERROR_NONE: c_int = 0;
+const ERROR_NOT_FOUND: c_int = 1;
+const ERROR_BAD_FORMAT: c_int = 2;
+const ERROR_UNSUPPORTED_VERSION: c_int = 3;
+const ERROR_IO: c_int = 4;
+
+/// Out-parameter filled by the shim when KyTea throws.
+#[repr(C)]
+pub(super) struct RawError {
+    code: c_int,
+    message: *mut c_char,
+}
+
+impl RawError {
+    pub(super) fn new() -> Self {
+        Self {
+            code: ERROR_NONE,
+            message: std::ptr::null_mut(),
+        }
+    }
+
+    pub(super) fn into_result(self) -> Result<(), ModelError> {
+        if self.code == ERROR_NONE {
+            return Ok(());
+        }
+
+        let msg = if self.message.is_null() {
+            String::new()
+        } else {
+            unsafe { CStr::from_ptr(self.message) }
+                .to_string_lossy()
+                .into_owned()
+        };
+
+        Err(match self.code {
+            ERROR_NOT_FOUND => ModelError::NotFound(msg),
+            ERROR_BAD_FORMAT => ModelError::BadFormat(msg),
+            ERROR_UNSUPPORTED_VERSION => ModelError::UnsupportedVersion(msg),
+            ERROR_IO => ModelError::Io(msg),
+            _ => ModelError::Kytea(msg),
+        })
+    }
+}
+
+impl Drop for RawError {
+    fn drop(&mut self) {
+        if !self.message.is_null() {
+            unsafe {
+                libc::free(self.message as *mut libc::c_void);
+            }
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use libc::c_int;
+
+use crate::format::OutputFormat;
+
+impl OutputFormat {
+    pub(super) fn to_raw(self) -> c_int {
+        match self {
+            Self::Full => 0,
+            Self::Tok => 1,
+            Self::Part => 2,
+            Self::Conf => 3,
+            Self::Eda => 4,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn raw_formats() {
+        // Same order as the output formats of the shim.
+        let formats = [
+            OutputFormat::Full,
+            OutputFormat::Tok,
+            OutputFormat::Part,
+            OutputFormat::Conf,
+            OutputFormat::Eda,
+        ];
+        let raw = formats.iter().map(|f| f.to_raw()).collect::<Vec<_>>();
+        assert_eq!(raw, [0, 1, 2, 3, 4]);
+    }
+}
@@ -0,0 +1,75 @@
+use libc::c_int;
+
+use std::ffi::CStr;
+
+use super::{
+    delete_strings, kytea_dictionary_size, kytea_do_tag, kytea_do_tags, kytea_do_ws,
+    kytea_encoding, kytea_num_features, kytea_num_tags, kytea_tags, strings_get, strings_len,
+    Encoding, Model, VoidPtr,
+};
+
+/// Introspection of the loaded model.
+impl Model {
+    /// The number of tag levels, e.g. 2 for the PoS and the pronunciation.
+    pub fn num_tags(&self) -> usize {
+        unsafe { kytea_num_tags(self.void_kytea) as usize }
+    }
+
+    /// Every tag of the tag level `lev` in the dictionary of the model, sorted.
+    ///
+    /// Tags are collected from the dictionary, so a tag that the global model gives only to
+    /// unknown words is missing; `model::KyteaModel::tags()` reads the global tags from the model
+    /// file and includes them.
+    pub fn tags(&self, lev: usize) -> Vec<String> {
+        unsafe {
+            let void_strings = kytea_tags(self.void_kytea, lev as c_int);
+            let tags = (0..strings_len(void_strings))
+                .map(|i| strings_get(void_strings, i).decode(self.encoding))
+                .collect();
+            delete_strings(void_strings);
+            tags
+        }
+    }
+
+    /// The encoding of the model; text is transcoded from and to UTF-8 if it is not UTF-8.
+    #[inline]
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// Whether the model segments words.
+    pub fn does_ws(&self) -> bool {
+        unsafe { kytea_do_ws(self.void_kytea) != 0 }
+    }
+
+    /// Whether the model tags words.
+    pub fn does_tags(&self) -> bool {
+        unsafe { kytea_do_tags(self.void_kytea) != 0 }
+    }
+
+    /// Whether the model computes the tag level `lev`.
+    pub fn does_tag(&self, lev: usize) -> bool {
+        self.does_tags()
+            && lev < self.num_tags()
+            && unsafe { kytea_do_tag(self.void_kytea, lev as c_int) != 0 }
+    }
+
+    /// The number of features of the word segmentation model.
+    pub fn num_features(&self) -> usize {
+        unsafe { kytea_num_features(self.void_kytea) }
+    }
+
+    /// The number of words in the dictionary of the model, including user dictionaries.
+    pub fn dictionary_size(&self) -> usize {
+        unsafe { kytea_dictionary_size(self.void_kytea) }
+    }
+}
+
+/// # Safety
+/// `void_kytea` must be a model returned from `new_kytea`.
+pub(super) unsafe fn read_encoding(void_kytea: VoidPtr) -> Encoding {
+    CStr::from_ptr(kytea_encoding(void_kytea))
+        .to_str()
+        .map(Encoding::from_kytea_name)
+        .unwrap_or(Encoding::Unknown)
+}
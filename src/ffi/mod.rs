@@ -0,0 +1,714 @@
+use libc::c_char;
+use libc::c_double;
+use libc::c_int;
+use libc::c_void;
+use libc::size_t;
+type VoidPtr = *mut c_void;
+
+use std::borrow::Cow;
+use std::ffi::{CStr, CString};
+use std::io::{BufRead, Write};
+use std::path::Path;
+use std::str::Utf8Error;
+use std::string::FromUtf8Error;
+
+use crate::{Boundary, ModelResolver};
+
+mod config;
+pub use config::ModelConfig;
+use config::RawConfig;
+
+mod error;
+pub use error::ModelError;
+use error::RawError;
+
+mod format;
+pub use crate::format::OutputFormat;
+
+mod info;
+pub use crate::Encoding;
+
+mod pool;
+pub use pool::{ModelPool, PooledModel};
+
+mod stream;
+use stream::Callbacks;
+
+mod train;
+use train::RawTrainConfig;
+pub use train::{AnnotatedSentence, CorpusFormat, ModelFormat, Solver, Trainer};
+
+mod word;
+pub use crate::word::{Sentence, Word, WordBoundary, WordCandidates};
+use word::Words;
+
+#[repr(C)]
+struct Str {
+    ptr: *const c_char,
+    size: c_int,
+}
+
+#[repr(C)]
+struct Span {
+    begin: size_t,
+    end: size_t,
+}
+
+#[repr(C)]
+struct RawBoundary {
+    offset: size_t,
+    confidence: c_double,
+}
+
+#[repr(C)]
+struct Constraint {
+    offset: size_t,
+    bound: c_int,
+}
+
+#[repr(C)]
+struct Candidate {
+    tag: Str,
+    score: c_double,
+}
+
+#[link(name = "ckytea")]
+extern "C" {
+    fn new_kytea(model: *const c_char, config: *const RawConfig, err: *mut RawError) -> VoidPtr;
+    fn delete_kytea(void_kytea: VoidPtr);
+
+    fn new_ostream() -> VoidPtr;
+    fn new_istream(input: *const c_char) -> VoidPtr;
+    fn new_istream_with_len(input: *const c_char, len: size_t) -> VoidPtr;
+    fn delete_ostream(void_stream: VoidPtr);
+    fn delete_istream(void_stream: VoidPtr);
+
+    fn rewind_ostream(void_stream: VoidPtr);
+    fn ostream_rust(void_stream: VoidPtr) -> Str;
+
+    fn run_kytea_str_str(
+        void_kytea: VoidPtr,
+        input: VoidPtr,
+        output: VoidPtr,
+        format: c_int,
+        err: *mut RawError,
+    );
+    fn run_kytea_file_str(
+        void_kytea: VoidPtr,
+        input: *const c_char,
+        output: VoidPtr,
+        format: c_int,
+        err: *mut RawError,
+    );
+    fn run_kytea_str_file(
+        void_kytea: VoidPtr,
+        input: VoidPtr,
+        output: *const c_char,
+        format: c_int,
+        err: *mut RawError,
+    );
+    fn run_kytea_file_file(
+        void_kytea: VoidPtr,
+        input: *const c_char,
+        output: *const c_char,
+        format: c_int,
+        err: *mut RawError,
+    );
+
+    fn run_kytea_str_words(void_kytea: VoidPtr, input: VoidPtr, err: *mut RawError) -> VoidPtr;
+    fn run_kytea_file_words(
+        void_kytea: VoidPtr,
+        input: *const c_char,
+        err: *mut RawError,
+    ) -> VoidPtr;
+    fn delete_words(void_words: VoidPtr);
+
+    fn words_len(void_words: VoidPtr) -> size_t;
+    fn words_surface(void_words: VoidPtr, i: size_t) -> Str;
+    fn words_span(void_words: VoidPtr, i: size_t) -> Span;
+    fn words_num_tags(void_words: VoidPtr, i: size_t) -> size_t;
+    fn words_num_candidates(void_words: VoidPtr, i: size_t, lev: size_t) -> size_t;
+    fn words_candidate(void_words: VoidPtr, i: size_t, lev: size_t, k: size_t) -> Candidate;
+
+    fn run_kytea_constrained(
+        void_kytea: VoidPtr,
+        line: *const c_char,
+        len: size_t,
+        constraints: *const Constraint,
+        num_constraints: size_t,
+        err: *mut RawError,
+    ) -> VoidPtr;
+
+    fn words_num_boundaries(void_words: VoidPtr) -> size_t;
+    fn words_boundary(void_words: VoidPtr, i: size_t) -> RawBoundary;
+
+    fn run_kytea_callback(
+        void_kytea: VoidPtr,
+        read_line: extern "C" fn(VoidPtr, *mut Str) -> c_int,
+        write_bytes: extern "C" fn(VoidPtr, *const c_char, size_t) -> c_int,
+        ctx: VoidPtr,
+        format: c_int,
+        err: *mut RawError,
+    );
+
+    fn kytea_num_tags(void_kytea: VoidPtr) -> c_int;
+    fn kytea_do_ws(void_kytea: VoidPtr) -> c_int;
+    fn kytea_do_tags(void_kytea: VoidPtr) -> c_int;
+    fn kytea_do_tag(void_kytea: VoidPtr, lev: c_int) -> c_int;
+    fn kytea_encoding(void_kytea: VoidPtr) -> *const c_char;
+    fn kytea_num_features(void_kytea: VoidPtr) -> size_t;
+    fn kytea_dictionary_size(void_kytea: VoidPtr) -> size_t;
+    fn kytea_tags(void_kytea: VoidPtr, lev: c_int) -> VoidPtr;
+
+    fn delete_strings(void_strings: VoidPtr);
+    fn strings_len(void_strings: VoidPtr) -> size_t;
+    fn strings_get(void_strings: VoidPtr, i: size_t) -> Str;
+
+    fn train_kytea(model: *const c_char, config: *const RawTrainConfig, err: *mut RawError);
+}
+
+pub struct Model {
+    void_kytea: VoidPtr,
+    encoding: Encoding,
+}
+
+unsafe impl Send for Model {}
+unsafe impl Sync for Model {}
+
+impl Model {
+    /// # Panics
+    /// Panics if KyTea fails to read the model. Use [`Model::try_new()`] to handle the error.
+    pub fn new(model: impl AsRef<Path>) -> Self {
+        match Self::try_new(model) {
+            Ok(model) => model,
+            Err(e) => panic!("failed to load the KyTea model: {}", e),
+        }
+    }
+
+    #[inline]
+    pub fn try_new(model: impl AsRef<Path>) -> Result<Self, ModelError> {
+        Self::with_config(model, &ModelConfig::default())
+    }
+
+    /// Loads the first model found by `resolver`, e.g. `Model::find(&ModelResolver::new())`.
+    #[inline]
+    pub fn find(resolver: &ModelResolver) -> Result<Self, ModelError> {
+        Self::try_new(resolver.resolve()?)
+    }
+
+    pub fn with_config(model: impl AsRef<Path>, config: &ModelConfig) -> Result<Self, ModelError> {
+        let model = model.as_ref();
+        let path = path_to_cstring(model)?;
+        // The tags of the config are passed in the encoding of the model; a model whose header
+        // cannot be read is reported by KyTea.
+        let encoding = Encoding::of_model(model).unwrap_or(Encoding::Utf8);
+        let mut err = RawError::new();
+        let void_kytea = config.with_raw(encoding, |config| unsafe {
+            new_kytea(path.as_ptr(), config, &mut err)
+        })?;
+        match err.into_result() {
+            Ok(()) => Ok(Self {
+                void_kytea,
+                encoding: unsafe { info::read_encoding(void_kytea) },
+            }),
+            // KyTea says "could not open" also when the file exists but is not readable.
+            Err(ModelError::NotFound(msg)) if model.exists() => Err(ModelError::Io(msg)),
+            Err(e) => Err(e),
+        }
+    }
+
+    #[inline]
+    pub fn output() -> Ostream {
+        Ostream::new()
+    }
+
+    #[inline]
+    pub fn tokenize_to_str(
+        &mut self,
+        input: &Istream,
+        output: &mut Ostream,
+    ) -> Result<(), ModelError> {
+        self.tokenize_to_str_with_format(input, output, OutputFormat::Full)
+    }
+
+    pub fn tokenize_to_str_with_format(
+        &mut self,
+        input: &Istream,
+        output: &mut Ostream,
+        format: OutputFormat,
+    ) -> Result<(), ModelError> {
+        let mut err = RawError::new();
+        let format = format.to_raw();
+        unsafe {
+            match input {
+                Istream::File(input) => run_kytea_file_str(
+                    self.void_kytea,
+                    *input,
+                    output.void_stream,
+                    format,
+                    &mut err,
+                ),
+                Istream::Buf(input) => run_kytea_str_str(
+                    self.void_kytea,
+                    *input,
+                    output.void_stream,
+                    format,
+                    &mut err,
+                ),
+            };
+        }
+        err.into_result()
+    }
+
+    /// Tokenizes `text` and returns the output in the same format as [`Model::tokenize_to_str()`].
+    ///
+    /// The last line is terminated with a newline if it is not, as KyTea expects.
+    #[inline]
+    pub fn tokenize(&mut self, text: &str) -> Result<String, ModelError> {
+        self.tokenize_with_format(text, OutputFormat::Full)
+    }
+
+    pub fn tokenize_with_format(
+        &mut self,
+        text: &str,
+        format: OutputFormat,
+    ) -> Result<String, ModelError> {
+        let text = terminate_line(text);
+        let encoded = self.encoding.encode(&text)?;
+        let input = Istream::from_bytes(&encoded.bytes);
+        let mut output = Ostream::new();
+        self.tokenize_to_str_with_format(&input, &mut output, format)?;
+        if self.encoding.is_utf8() {
+            output
+                .into_string()
+                .map_err(|e| ModelError::Kytea(format!("the output is not valid UTF-8: {}", e)))
+        } else {
+            Ok(self.encoding.decode(output.as_bytes()).into_owned())
+        }
+    }
+
+    /// Tokenizes `text` as [`Model::tokenize_to_words()`] does.
+    ///
+    /// Offsets are byte indices in `text` even if the model is not for UTF-8.
+    pub fn tokenize_words(&mut self, text: &str) -> Result<Vec<Word>, ModelError> {
+        let text = terminate_line(text);
+        let encoded = self.encoding.encode(&text)?;
+        let words = self.tokenize_to_words(&Istream::from_bytes(&encoded.bytes))?;
+        Ok(words
+            .into_iter()
+            .map(|mut word| {
+                word.offset_from = encoded.to_text_offset(word.offset_from);
+                word.offset_to = encoded.to_text_offset(word.offset_to);
+                word
+            })
+            .collect())
+    }
+
+    /// Reads `input` line by line and writes each analyzed sentence to `output`, in the same
+    /// format as [`Model::tokenize_to_str()`].
+    ///
+    /// Sentences are passed across the FFI one by one, so the whole input is never copied.
+    /// `input` must be UTF-8 and `output` is written in UTF-8, whatever the model's encoding is.
+    #[inline]
+    pub fn tokenize_reader<R: BufRead, W: Write>(
+        &mut self,
+        input: R,
+        output: W,
+    ) -> Result<(), ModelError> {
+        self.tokenize_reader_with_format(input, output, OutputFormat::Full)
+    }
+
+    pub fn tokenize_reader_with_format<R: BufRead, W: Write>(
+        &mut self,
+        input: R,
+        output: W,
+        format: OutputFormat,
+    ) -> Result<(), ModelError> {
+        let mut callbacks = Callbacks::new(input, output, self.encoding);
+        let mut err = RawError::new();
+        unsafe {
+            run_kytea_callback(
+                self.void_kytea,
+                stream::read_line::<R, W>,
+                stream::write_bytes::<R, W>,
+                &mut callbacks as *mut Callbacks<R, W> as VoidPtr,
+                format.to_raw(),
+                &mut err,
+            );
+        }
+        let result = err.into_result();
+        let mut output = callbacks
+            .finish()
+            .map_err(|e| ModelError::Io(e.to_string()))?;
+        result?;
+        output.flush().map_err(|e| ModelError::Io(e.to_string()))
+    }
+
+    /// Tokenizes the input and returns the analyzed words with their byte offsets in the input.
+    ///
+    /// Unlike [`Model::tokenize_to_str()`], words are read directly from KyTea and no escaped
+    /// text has to be parsed.
+    pub fn tokenize_to_words(&mut self, input: &Istream) -> Result<Vec<Word>, ModelError> {
+        self.run_words(input).map(|words| words.to_vec())
+    }
+
+    /// Same as [`Model::tokenize_to_words()`], but keeps every tag candidate with its score
+    /// instead of the best tag only.
+    pub fn tokenize_to_candidates(
+        &mut self,
+        input: &Istream,
+    ) -> Result<Vec<WordCandidates>, ModelError> {
+        self.run_words(input).map(|words| words.to_candidates())
+    }
+
+    /// Tokenizes the input and returns the confidence of every gap between two characters.
+    ///
+    /// The confidence is the margin of the word segmentation classifier: a gap is a word boundary
+    /// if it is positive, and the smaller its absolute value, the more ambiguous the gap is.
+    pub fn tokenize_to_boundaries(
+        &mut self,
+        input: &Istream,
+    ) -> Result<Vec<WordBoundary>, ModelError> {
+        self.run_words(input).map(|words| words.to_boundaries())
+    }
+
+    /// Tokenizes `text` with some gaps fixed in advance, like KyTea's partial annotation input.
+    ///
+    /// Each constraint is the offset (byte index) of a gap in `text` and its boundary;
+    /// [`Boundary::Bound`] forces a word boundary, [`Boundary::NoBound`] forbids one, and
+    /// [`Boundary::Unknown`] is left to KyTea. The other gaps are predicted as usual.
+    ///
+    /// Each line is analyzed as a sentence, so gaps around newlines are always boundaries.
+    pub fn tokenize_constrained(
+        &mut self,
+        text: &str,
+        constraints: &[(usize, Boundary)],
+    ) -> Result<Vec<Word>, ModelError> {
+        let mut forced = Vec::with_capacity(constraints.len());
+        for &(offset, boundary) in constraints {
+            if offset == 0 || offset >= text.len() || !text.is_char_boundary(offset) {
+                return Err(ModelError::InvalidInput(format!(
+                    "{} is not a gap between two characters",
+                    offset
+                )));
+            }
+            let bound = match boundary {
+                Boundary::Bound => 1,
+                Boundary::NoBound => -1,
+                Boundary::Unknown => continue,
+            };
+            forced.push(Constraint { offset, bound });
+        }
+        forced.sort_by_key(|constraint| constraint.offset);
+
+        let mut words = Vec::new();
+        let mut line_start = 0;
+        for line in text.split('\n') {
+            let line_end = line_start + line.len();
+            let line_constraints = forced
+                .iter()
+                .filter(|c| line_start < c.offset && c.offset < line_end)
+                .map(|c| Constraint {
+                    offset: c.offset - line_start,
+                    bound: c.bound,
+                })
+                .collect::<Vec<_>>();
+
+            let encoded = self.encoding.encode(line).map_err(|mut e| {
+                e.offset += line_start;
+                e
+            })?;
+            let line_constraints = line_constraints
+                .into_iter()
+                .map(|c| Constraint {
+                    offset: encoded.to_bytes_offset(c.offset),
+                    bound: c.bound,
+                })
+                .collect::<Vec<_>>();
+
+            let mut err = RawError::new();
+            let void_words = unsafe {
+                run_kytea_constrained(
+                    self.void_kytea,
+                    encoded.bytes.as_ptr() as *const c_char,
+                    encoded.bytes.len(),
+                    line_constraints.as_ptr(),
+                    line_constraints.len(),
+                    &mut err,
+                )
+            };
+            err.into_result()?;
+            let line_words = unsafe { Words::from_raw(void_words, self.encoding) };
+            words.extend(line_words.to_vec().into_iter().map(|mut word| {
+                word.offset_from = encoded.to_text_offset(word.offset_from) + line_start;
+                word.offset_to = encoded.to_text_offset(word.offset_to) + line_start;
+                word
+            }));
+
+            line_start = line_end + 1;
+        }
+        Ok(words)
+    }
+
+    /// Tokenizes each document separately and returns exactly one sentence per document, in the
+    /// same order, even if a document is empty or contains newlines.
+    ///
+    /// Word offsets are relative to the beginning of each document.
+    pub fn tokenize_batch(
+        &mut self,
+        docs: &[impl AsRef<str>],
+    ) -> Result<Vec<Sentence>, ModelError> {
+        docs.iter()
+            .map(|doc| {
+                self.tokenize_constrained(doc.as_ref(), &[])
+                    .map(|words| Sentence { words })
+            })
+            .collect()
+    }
+
+    fn run_words(&mut self, input: &Istream) -> Result<Words, ModelError> {
+        let mut err = RawError::new();
+        let void_words = unsafe {
+            match input {
+                Istream::File(input) => run_kytea_file_words(self.void_kytea, *input, &mut err),
+                Istream::Buf(input) => run_kytea_str_words(self.void_kytea, *input, &mut err),
+            }
+        };
+        err.into_result()?;
+        let words = unsafe { Words::from_raw(void_words, self.encoding) };
+        Ok(words)
+    }
+
+    #[inline]
+    pub fn tokenize_to_file(&mut self, input: &Istream, output: &CStr) -> Result<(), ModelError> {
+        self.tokenize_to_file_with_format(input, output, OutputFormat::Full)
+    }
+
+    pub fn tokenize_to_file_with_format(
+        &mut self,
+        input: &Istream,
+        output: &CStr,
+        format: OutputFormat,
+    ) -> Result<(), ModelError> {
+        let mut err = RawError::new();
+        let format = format.to_raw();
+        unsafe {
+            match input {
+                Istream::File(input) => {
+                    run_kytea_file_file(self.void_kytea, *input, output.as_ptr(), format, &mut err)
+                }
+                Istream::Buf(input) => {
+                    run_kytea_str_file(self.void_kytea, *input, output.as_ptr(), format, &mut err)
+                }
+            };
+        }
+        err.into_result()
+    }
+}
+
+impl crate::Tokenize for Model {
+    type Error = ModelError;
+
+    #[inline]
+    fn tokenize(&mut self, text: &str) -> Result<String, ModelError> {
+        Model::tokenize(self, text)
+    }
+
+    #[inline]
+    fn tokenize_words(&mut self, text: &str) -> Result<Vec<Word>, ModelError> {
+        Model::tokenize_words(self, text)
+    }
+}
+
+/// Appends the newline that KyTea's corpus reader expects at the end of the last line.
+fn terminate_line(text: &str) -> Cow<'_, str> {
+    if text.is_empty() || text.ends_with('\n') {
+        Cow::Borrowed(text)
+    } else {
+        Cow::Owned(format!("{}\n", text))
+    }
+}
+
+fn path_to_cstring(path: &Path) -> Result<CString, ModelError> {
+    #[cfg(unix)]
+    let bytes = std::os::unix::ffi::OsStrExt::as_bytes(path.as_os_str());
+    #[cfg(not(unix))]
+    let bytes = path
+        .to_str()
+        .ok_or_else(|| {
+            ModelError::InvalidInput(format!("{} is not valid Unicode", path.display()))
+        })?
+        .as_bytes();
+    CString::new(bytes)
+        .map_err(|_| ModelError::InvalidInput(format!("{} contains a nul byte", path.display())))
+}
+
+impl Drop for Model {
+    fn drop(&mut self) {
+        unsafe {
+            delete_kytea(self.void_kytea);
+        }
+    }
+}
+
+/// Input of KyTea, passed as is: the bytes must be in the [encoding](Model::encoding) of the
+/// model. The methods of [`Model`] taking `&str` transcode the text instead.
+pub enum Istream<'a> {
+    File(&'a c_char),
+    Buf(VoidPtr),
+}
+
+unsafe impl Send for Istream<'_> {}
+unsafe impl Sync for Istream<'_> {}
+
+impl<'a> Istream<'a> {
+    /// # Safety
+    /// The provided slice **must** be nul-terminated and not contain any interior nul bytes.
+    ///
+    /// See [`CStr::from_bytes_with_nul_unchecked()`] for details.
+    pub unsafe fn from_file_unchecked(fname: &'a [u8]) -> Self {
+        let fname = CStr::from_bytes_with_nul_unchecked(fname);
+        Self::from_file(fname)
+    }
+
+    pub fn from_file(fname: &'a CStr) -> Self {
+        Self::File(unsafe { &*fname.as_ptr() })
+    }
+
+    /// # Safety
+    /// The provided slice **must** be nul-terminated and not contain any interior nul bytes.
+    ///
+    /// See [`CStr::from_bytes_with_nul_unchecked()`] for details.
+    pub unsafe fn from_buffer_unchecked(bytes: &[u8]) -> Self {
+        let bytes = CStr::from_bytes_with_nul_unchecked(bytes);
+        Self::from_buffer(bytes)
+    }
+
+    /// # Safety
+    /// The provided slice **must** be nul-terminated and not contain any interior nul bytes.
+    ///
+    /// See [`CStr::from_bytes_with_nul_unchecked()`] for details.
+    pub fn from_buffer(bytes: &CStr) -> Self {
+        unsafe { Self::Buf(new_istream(bytes.as_ptr())) }
+    }
+
+    /// Copies the bytes into a new input buffer; they need not be nul-terminated.
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        unsafe {
+            Self::Buf(new_istream_with_len(
+                bytes.as_ptr() as *const c_char,
+                bytes.len(),
+            ))
+        }
+    }
+
+    #[inline]
+    pub fn from_text(text: &str) -> Self {
+        Self::from_bytes(text.as_bytes())
+    }
+}
+
+impl Drop for Istream<'_> {
+    fn drop(&mut self) {
+        if let &mut Self::Buf(void_stream) = self {
+            unsafe {
+                delete_istream(void_stream);
+            }
+        }
+    }
+}
+
+/// Output of KyTea, in the [encoding](Model::encoding) of the model.
+pub struct Ostream {
+    void_stream: VoidPtr,
+}
+
+unsafe impl Send for Ostream {}
+unsafe impl Sync for Ostream {}
+
+impl Default for Ostream {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Ostream {
+    pub fn new() -> Self {
+        unsafe {
+            Self {
+                void_stream: new_ostream(),
+            }
+        }
+    }
+
+    /// The output written so far.
+    ///
+    /// The slice borrows `self`, so it cannot outlive a rewind, another tokenization or the
+    /// stream itself.
+    pub fn as_bytes(&self) -> &[u8] {
+        // SAFETY: the buffer is not modified nor freed while `self` is borrowed.
+        unsafe { ostream_rust(self.void_stream).as_bytes() }
+    }
+
+    pub fn as_str(&self) -> Result<&str, Utf8Error> {
+        std::str::from_utf8(self.as_bytes())
+    }
+
+    /// # Safety
+    /// The output must be valid UTF-8, e.g. the input bytes ([`Istream`]) are valid UTF-8 and
+    /// the model is for UTF-8.
+    pub unsafe fn as_str_unchecked(&self) -> &str {
+        std::str::from_utf8_unchecked(self.as_bytes())
+    }
+
+    /// Copies the output into a new `String`, failing if it is not valid UTF-8.
+    pub fn to_string(&self) -> Result<String, Utf8Error> {
+        self.as_str().map(String::from)
+    }
+
+    /// Same as [`Ostream::to_string()`], but consumes the stream.
+    pub fn into_string(self) -> Result<String, FromUtf8Error> {
+        String::from_utf8(self.as_bytes().to_vec())
+    }
+
+    pub fn rewind(&mut self) {
+        unsafe {
+            rewind_ostream(self.void_stream);
+        }
+    }
+}
+
+impl Drop for Ostream {
+    fn drop(&mut self) {
+        unsafe {
+            delete_ostream(self.void_stream);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn terminate_last_line() {
+        assert_eq!(terminate_line("東京"), "東京\n");
+        assert_eq!(terminate_line("東京\n京都"), "東京\n京都\n");
+        assert!(matches!(terminate_line("東京\n"), Cow::Borrowed("東京\n")));
+        assert!(matches!(terminate_line(""), Cow::Borrowed("")));
+    }
+
+    #[test]
+    fn paths_to_cstrings() {
+        let path = Path::new("models/東京.bin");
+        assert_eq!(
+            path_to_cstring(path).unwrap().as_bytes(),
+            "models/東京.bin".as_bytes()
+        );
+        assert!(matches!(
+            path_to_cstring(Path::new("model\0.bin")),
+            Err(ModelError::InvalidInput(_))
+        ));
+    }
+}
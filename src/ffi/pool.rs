@@ -0,0 +1,160 @@
+use std::ops::{Deref, DerefMut};
+use std::path::Path;
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::time::Duration;
+
+use super::{Istream, Model, ModelConfig, ModelError, Word};
+
+/// A fixed number of [`Model`]s loaded from the same file, shared between threads.
+///
+/// A model is checked out with [`ModelPool::get()`] (or [`ModelPool::try_get()`]) and returned
+/// to the pool when the guard is dropped.
+pub struct ModelPool {
+    models: Mutex<Vec<Model>>,
+    returned: Condvar,
+    size: usize,
+}
+
+impl ModelPool {
+    #[inline]
+    pub fn new(model: impl AsRef<Path>, size: usize) -> Result<Self, ModelError> {
+        Self::with_config(model, &ModelConfig::default(), size)
+    }
+
+    pub fn with_config(
+        model: impl AsRef<Path>,
+        config: &ModelConfig,
+        size: usize,
+    ) -> Result<Self, ModelError> {
+        let model = model.as_ref();
+        if size == 0 {
+            return Err(ModelError::InvalidInput(String::from(
+                "the size of a model pool must be positive",
+            )));
+        }
+
+        let models = (0..size)
+            .map(|_| Model::with_config(model, config))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            models: Mutex::new(models),
+            returned: Condvar::new(),
+            size,
+        })
+    }
+
+    /// The number of models, including the checked out ones.
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// The number of models not checked out.
+    pub fn available(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Checks out a model, blocking until one is returned if all are busy.
+    pub fn get(&self) -> PooledModel<'_> {
+        let mut models = self.lock();
+        loop {
+            if let Some(model) = models.pop() {
+                return PooledModel::new(self, model);
+            }
+            models = self
+                .returned
+                .wait(models)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+    }
+
+    /// Checks out a model, or returns `None` immediately if all are busy.
+    pub fn try_get(&self) -> Option<PooledModel<'_>> {
+        let model = self.lock().pop()?;
+        Some(PooledModel::new(self, model))
+    }
+
+    /// Checks out a model, or returns `None` if none is returned within `timeout`.
+    pub fn get_timeout(&self, timeout: Duration) -> Option<PooledModel<'_>> {
+        let models = self.lock();
+        let (mut models, _) = self
+            .returned
+            .wait_timeout_while(models, timeout, |models| models.is_empty())
+            .unwrap_or_else(|e| e.into_inner());
+        let model = models.pop()?;
+        Some(PooledModel::new(self, model))
+    }
+
+    /// [`Model::tokenize()`] on a model of the pool, blocking while all are busy.
+    pub fn tokenize(&self, text: &str) -> Result<String, ModelError> {
+        self.get().tokenize(text)
+    }
+
+    /// [`Model::tokenize_words()`] on a model of the pool, blocking while all are busy.
+    ///
+    /// Offsets are byte indices in `text` even if the model is not for UTF-8.
+    pub fn tokenize_words(&self, text: &str) -> Result<Vec<Word>, ModelError> {
+        self.get().tokenize_words(text)
+    }
+
+    /// [`Model::tokenize_to_words()`] on a model of the pool, blocking while all are busy.
+    pub fn tokenize_to_words(&self, input: &Istream) -> Result<Vec<Word>, ModelError> {
+        self.get().tokenize_to_words(input)
+    }
+
+    /// [`Model::tokenize_to_words()`] on a model of the pool, or `None` if all are busy.
+    pub fn try_tokenize_to_words(&self, input: &Istream) -> Option<Result<Vec<Word>, ModelError>> {
+        self.try_get()
+            .map(|mut model| model.tokenize_to_words(input))
+    }
+
+    // A panic while holding the lock cannot leave the vector inconsistent.
+    fn lock(&self) -> MutexGuard<'_, Vec<Model>> {
+        self.models.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn put_back(&self, model: Model) {
+        self.lock().push(model);
+        self.returned.notify_one();
+    }
+}
+
+/// A model checked out from a [`ModelPool`], returned to it on drop.
+pub struct PooledModel<'a> {
+    pool: &'a ModelPool,
+    model: Option<Model>,
+}
+
+impl<'a> PooledModel<'a> {
+    fn new(pool: &'a ModelPool, model: Model) -> Self {
+        Self {
+            pool,
+            model: Some(model),
+        }
+    }
+}
+
+impl Deref for PooledModel<'_> {
+    type Target = Model;
+
+    #[inline]
+    fn deref(&self) -> &Model {
+        // `model` is `None` only in `drop()`.
+        self.model.as_ref().unwrap()
+    }
+}
+
+impl DerefMut for PooledModel<'_> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Model {
+        self.model.as_mut().unwrap()
+    }
+}
+
+impl Drop for PooledModel<'_> {
+    fn drop(&mut self) {
+        if let Some(model) = self.model.take() {
+            self.pool.put_back(model);
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use libc::c_char;
+use libc::c_int;
+use libc::c_void;
+use libc::size_t;
+
+use std::io::{BufRead, Write};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use super::{Encoding, Str};
+
+/// State shared with the shim while streaming, passed as `ctx` to the callbacks.
+pub(super) struct Callbacks<R, W> {
+    reader: R,
+    writer: W,
+    encoding: Encoding,
+    line: Vec<u8>,
+    error: Option<std::io::Error>,
+    panic: Option<Box<dyn std::any::Any + Send>>,
+}
+
+impl<R: BufRead, W: Write> Callbacks<R, W> {
+    pub(super) fn new(reader: R, writer: W, encoding: Encoding) -> Self {
+        Self {
+            reader,
+            writer,
+            encoding,
+            line: Vec::new(),
+            error: None,
+            panic: None,
+        }
+    }
+
+    /// Returns the error raised in the callbacks, and resumes the panic if any.
+    pub(super) fn finish(self) -> Result<W, std::io::Error> {
+        if let Some(panic) = self.panic {
+            std::panic::resume_unwind(panic);
+        }
+        match self.error {
+            Some(e) => Err(e),
+            None => Ok(self.writer),
+        }
+    }
+
+    fn read_line(&mut self) -> std::io::Result<bool> {
+        self.line.clear();
+        if self.reader.read_until(b'\n', &mut self.line)? == 0 {
+            return Ok(false);
+        }
+        if self.line.last() == Some(&b'\n') {
+            self.line.pop();
+        }
+        if !self.encoding.is_utf8() {
+            let text = std::str::from_utf8(&self.line)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+            let encoded = self
+                .encoding
+                .encode(text)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+            self.line = encoded.bytes.into_owned();
+        }
+        Ok(true)
+    }
+
+    /// Writes a sentence analyzed by KyTea, transcoded to UTF-8.
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<()> {
+        if self.encoding.is_utf8() {
+            self.writer.write_all(bytes)
+        } else {
+            self.writer
+                .write_all(self.encoding.decode(bytes).as_bytes())
+        }
+    }
+
+    /// Catches errors and panics, which must not cross the FFI boundary.
+    fn guard(&mut self, f: impl FnOnce(&mut Self) -> std::io::Result<c_int>) -> c_int {
+        match catch_unwind(AssertUnwindSafe(|| f(self))) {
+            Ok(Ok(status)) => status,
+            Ok(Err(e)) => {
+                self.error = Some(e);
+                -1
+            }
+            Err(panic) => {
+                self.panic = Some(panic);
+                -1
+            }
+        }
+    }
+}
+
+pub(super) extern "C" fn read_line<R: BufRead, W: Write>(
+    ctx: *mut c_void,
+    line: *mut Str,
+) -> c_int {
+    // SAFETY: `ctx` is the `Callbacks` passed to `run_kytea_callback`.
+    let callbacks = unsafe { &mut *(ctx as *mut Callbacks<R, W>) };
+    callbacks.guard(|callbacks| {
+        if !callbacks.read_line()? {
+            return Ok(0);
+        }
+        unsafe {
+            (*line).ptr = callbacks.line.as_ptr() as *const c_char;
+            (*line).size = callbacks.line.len() as c_int;
+        }
+        Ok(1)
+    })
+}
+
+pub(super) extern "C" fn write_bytes<R: BufRead, W: Write>(
+    ctx: *mut c_void,
+    ptr: *const c_char,
+    size: size_t,
+) -> c_int {
+    // SAFETY: `ctx` is the `Callbacks` passed to `run_kytea_callback`.
+    let callbacks = unsafe { &mut *(ctx as *mut Callbacks<R, W>) };
+    // SAFETY: the shim passes `size` bytes of its buffer.
+    let bytes = unsafe { std::slice::from_raw_parts(ptr as *const u8, size) };
+    callbacks.guard(|callbacks| callbacks.write(bytes).map(|()| 0))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn transcode_lines() {
+        let input = "東京\nに行く";
+        let mut callbacks = Callbacks::new(input.as_bytes(), Vec::new(), Encoding::EucJp);
+        assert!(callbacks.read_line().unwrap());
+        assert_eq!(callbacks.line, b"\xc5\xec\xb5\xfe");
+        assert!(callbacks.read_line().unwrap());
+        assert_eq!(callbacks.line, b"\xa4\xcb\xb9\xd4\xa4\xaf");
+        assert!(!callbacks.read_line().unwrap());
+
+        callbacks.write(b"\xc5\xec\xb5\xfe\n").unwrap();
+        assert_eq!(callbacks.finish().unwrap(), "東京\n".as_bytes());
+    }
+
+    #[test]
+    fn keep_errors_and_panics() {
+        let mut callbacks = Callbacks::new(&b""[..], Vec::new(), Encoding::Utf8);
+        let status = callbacks.guard(|_| {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "bad line",
+            ))
+        });
+        assert_eq!(status, -1);
+        assert_eq!(
+            callbacks.finish().unwrap_err().kind(),
+            std::io::ErrorKind::InvalidData
+        );
+
+        let mut callbacks = Callbacks::new(&b""[..], Vec::new(), Encoding::Utf8);
+        assert_eq!(callbacks.guard(|_| panic!("in a callback")), -1);
+        let panic = catch_unwind(AssertUnwindSafe(|| callbacks.finish())).unwrap_err();
+        assert_eq!(panic.downcast_ref::<&str>(), Some(&"in a callback"));
+    }
+}
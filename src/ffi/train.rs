@@ -0,0 +1,502 @@
+use libc::c_char;
+use libc::c_double;
+use libc::c_int;
+use libc::size_t;
+
+use std::ffi::CString;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use super::{path_to_cstring, train_kytea, ModelError, RawError, Word};
+use crate::dictionary::{write_word, FULL_WORD_DELIM};
+use crate::{Boundary, Encoding, ELEM_DELIM, ESCAPE, HAS_BOUND, NO_BOUND, TAG_DELIM, UNK_BOUND};
+
+/// Format of an annotated corpus file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CorpusFormat {
+    /// Fully annotated: `コーパス/名詞/こーぱす で/助詞/で`.
+    Full,
+    /// Partially annotated: `コ-ー-パ-ス|で あ-る`.
+    Part,
+}
+
+impl CorpusFormat {
+    fn to_raw(self) -> c_int {
+        match self {
+            Self::Full => 0,
+            Self::Part => 1,
+        }
+    }
+}
+
+/// Format of the model file written by the [`Trainer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ModelFormat {
+    Binary,
+    Text,
+}
+
+/// The liblinear solver used by KyTea, `-solver` of `train-kytea`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(i32)]
+pub enum Solver {
+    L2RegLogisticRegression = 0,
+    L2RegL2LossSvcDual = 1,
+    L2RegL2LossSvc = 2,
+    L2RegL1LossSvcDual = 3,
+    MulticlassSvm = 4,
+    L1RegL2LossSvc = 5,
+    L1RegLogisticRegression = 6,
+    L2RegLogisticRegressionDual = 7,
+}
+
+/// A sentence to train on, written to a temporary corpus by [`Trainer::train()`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum AnnotatedSentence {
+    /// Every word boundary and tag is known.
+    /// Only the surfaces and the tags of the words are used; tags after an empty one are ignored.
+    Full(Vec<Word>),
+    /// Only some gaps are annotated, as in [`Model::tokenize_constrained()`](super::Model::tokenize_constrained).
+    /// The text cannot contain a newline nor a delimiter of the partial annotation format,
+    /// `|`, `-`, ` `, `/`, `&` or `\`.
+    Part {
+        text: String,
+        boundaries: Vec<(usize, Boundary)>,
+    },
+}
+
+/// Trains a KyTea model, like `train-kytea`.
+///
+/// Options left unset keep the defaults of KyTea.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Trainer {
+    corpora: Vec<(PathBuf, CorpusFormat)>,
+    sentences: Vec<AnnotatedSentence>,
+    dictionaries: Vec<PathBuf>,
+    char_window: Option<u32>,
+    char_n: Option<u32>,
+    type_window: Option<u32>,
+    type_n: Option<u32>,
+    dictionary_n: Option<u32>,
+    solver: Option<Solver>,
+    epsilon: Option<f64>,
+    cost: Option<f64>,
+    bias: Option<bool>,
+    model_format: Option<ModelFormat>,
+    encoding: Option<Encoding>,
+}
+
+impl Trainer {
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// `-full` or `-part`: trains on a corpus file.
+    pub fn corpus(mut self, path: impl AsRef<Path>, format: CorpusFormat) -> Self {
+        self.corpora.push((path.as_ref().to_path_buf(), format));
+        self
+    }
+
+    /// Trains on a sentence given from Rust.
+    pub fn sentence(mut self, sentence: AnnotatedSentence) -> Self {
+        self.sentences.push(sentence);
+        self
+    }
+
+    pub fn sentences(mut self, sentences: impl IntoIterator<Item = AnnotatedSentence>) -> Self {
+        self.sentences.extend(sentences);
+        self
+    }
+
+    /// `-dict`: a dictionary file whose entries are used as features.
+    pub fn dictionary(mut self, path: impl AsRef<Path>) -> Self {
+        self.dictionaries.push(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// `-charw`: the window of character n-gram features.
+    pub fn char_window(mut self, n: u32) -> Self {
+        self.char_window = Some(n);
+        self
+    }
+
+    /// `-charn`: the maximum length of character n-gram features.
+    pub fn char_n(mut self, n: u32) -> Self {
+        self.char_n = Some(n);
+        self
+    }
+
+    /// `-typew`: the window of character type n-gram features.
+    pub fn type_window(mut self, n: u32) -> Self {
+        self.type_window = Some(n);
+        self
+    }
+
+    /// `-typen`: the maximum length of character type n-gram features.
+    pub fn type_n(mut self, n: u32) -> Self {
+        self.type_n = Some(n);
+        self
+    }
+
+    /// `-dicn`: words longer than `n` share the same dictionary features.
+    pub fn dictionary_n(mut self, n: u32) -> Self {
+        self.dictionary_n = Some(n);
+        self
+    }
+
+    /// `-solver`
+    pub fn solver(mut self, solver: Solver) -> Self {
+        self.solver = Some(solver);
+        self
+    }
+
+    /// `-eps`: the tolerance of the termination criterion.
+    pub fn epsilon(mut self, epsilon: f64) -> Self {
+        self.epsilon = Some(epsilon);
+        self
+    }
+
+    /// `-cost`: the cost of the constraints violation (inverse of the regularization).
+    pub fn cost(mut self, cost: f64) -> Self {
+        self.cost = Some(cost);
+        self
+    }
+
+    /// `-nobias`: trains without the bias term when `false`.
+    pub fn bias(mut self, bias: bool) -> Self {
+        self.bias = Some(bias);
+        self
+    }
+
+    /// `-modtext`: writes the model in the text format instead of the binary one.
+    pub fn model_format(mut self, format: ModelFormat) -> Self {
+        self.model_format = Some(format);
+        self
+    }
+
+    /// `-encode`: the encoding of the corpora, the dictionaries and the model, UTF-8 by default.
+    ///
+    /// The sentences given from Rust are transcoded; corpus and dictionary files must already be
+    /// in `encoding`, e.g. written by
+    /// [`write_dictionary_with_encoding()`](crate::dictionary::write_dictionary_with_encoding).
+    pub fn encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = Some(encoding);
+        self
+    }
+
+    /// Trains a model and writes it to `model`, which can then be loaded by
+    /// [`Model::try_new()`](super::Model::try_new).
+    pub fn train(&self, model: impl AsRef<Path>) -> Result<(), ModelError> {
+        let model = path_to_cstring(model.as_ref())?;
+
+        let encoding = self.encoding.unwrap_or(Encoding::Utf8);
+        let temp = TempCorpora::write(&self.sentences, encoding).map_err(|e| match e.kind() {
+            ErrorKind::InvalidInput => ModelError::InvalidInput(e.to_string()),
+            _ => ModelError::Io(format!("cannot write the temporary corpus: {}", e)),
+        })?;
+        let corpora = self
+            .corpora
+            .iter()
+            .chain(&temp.files)
+            .map(|(path, format)| Ok((path_to_cstring(path)?, *format)))
+            .collect::<Result<Vec<_>, ModelError>>()?;
+        let dictionaries = self
+            .dictionaries
+            .iter()
+            .map(|d| path_to_cstring(d))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let corpus_paths = corpora.iter().map(|(p, _)| p.as_ptr()).collect::<Vec<_>>();
+        let corpus_formats = corpora.iter().map(|(_, f)| f.to_raw()).collect::<Vec<_>>();
+        let dictionaries = dictionaries.iter().map(|d| d.as_ptr()).collect::<Vec<_>>();
+        let encoding_name = self
+            .encoding
+            .map(|encoding| CString::new(encoding.kytea_name()).expect("no nul byte"));
+
+        let config = RawTrainConfig {
+            corpora: corpus_paths.as_ptr(),
+            corpus_formats: corpus_formats.as_ptr(),
+            num_corpora: corpora.len(),
+            dictionaries: dictionaries.as_ptr(),
+            num_dictionaries: dictionaries.len(),
+            char_window: to_raw_int(self.char_window),
+            char_n: to_raw_int(self.char_n),
+            type_window: to_raw_int(self.type_window),
+            type_n: to_raw_int(self.type_n),
+            dictionary_n: to_raw_int(self.dictionary_n),
+            solver: self.solver.map(|s| s as c_int).unwrap_or(-1),
+            epsilon: self.epsilon.unwrap_or(-1.0),
+            cost: self.cost.unwrap_or(-1.0),
+            bias: self.bias.map(|b| b as c_int).unwrap_or(-1),
+            model_format: match self.model_format {
+                Some(ModelFormat::Binary) => b'B' as c_char,
+                Some(ModelFormat::Text) => b'T' as c_char,
+                None => 0,
+            },
+            encoding: encoding_name
+                .as_ref()
+                .map(|name| name.as_ptr())
+                .unwrap_or(std::ptr::null()),
+        };
+
+        let mut err = RawError::new();
+        unsafe {
+            train_kytea(model.as_ptr(), &config, &mut err);
+        }
+        err.into_result()
+    }
+}
+
+fn to_raw_int(n: Option<u32>) -> c_int {
+    n.map(|n| n as c_int).unwrap_or(-1)
+}
+
+/// Negative (or 0 for `model_format`, null for `encoding`) values are left to KyTea.
+#[repr(C)]
+pub(super) struct RawTrainConfig {
+    corpora: *const *const c_char,
+    corpus_formats: *const c_int,
+    num_corpora: size_t,
+    dictionaries: *const *const c_char,
+    num_dictionaries: size_t,
+    char_window: c_int,
+    char_n: c_int,
+    type_window: c_int,
+    type_n: c_int,
+    dictionary_n: c_int,
+    solver: c_int,
+    epsilon: c_double,
+    cost: c_double,
+    bias: c_int,
+    model_format: c_char,
+    encoding: *const c_char,
+}
+
+/// Corpus files of the sentences given from Rust, removed on drop.
+struct TempCorpora {
+    files: Vec<(PathBuf, CorpusFormat)>,
+}
+
+impl TempCorpora {
+    /// Writes the sentences in `encoding`, which the corpus reader of KyTea expects.
+    fn write(sentences: &[AnnotatedSentence], encoding: Encoding) -> std::io::Result<Self> {
+        let mut corpora = Self { files: Vec::new() };
+        let mut full = None;
+        let mut part = None;
+
+        for sentence in sentences {
+            let (writer, format) = match sentence {
+                AnnotatedSentence::Full(_) => (&mut full, CorpusFormat::Full),
+                AnnotatedSentence::Part { .. } => (&mut part, CorpusFormat::Part),
+            };
+            let writer = match writer {
+                Some(writer) => writer,
+                None => {
+                    let (path, file) = create_temp_file()?;
+                    corpora.files.push((path, format));
+                    writer.insert(BufWriter::new(file))
+                }
+            };
+
+            // Written in UTF-8 first, then transcoded line by line.
+            let mut line = Vec::new();
+            match sentence {
+                AnnotatedSentence::Full(words) => write_full(&mut line, words)?,
+                AnnotatedSentence::Part { text, boundaries } => {
+                    write_part(&mut line, text, boundaries)?
+                }
+            }
+            let line = String::from_utf8(line).expect("the writers write UTF-8");
+            writer.write_all(&encoding.encode(&line)?.bytes)?;
+        }
+
+        for writer in [full, part].iter_mut().flatten() {
+            writer.flush()?;
+        }
+        Ok(corpora)
+    }
+}
+
+/// Creates a new file in the temporary directory.
+///
+/// The name is predictable, so an existing file (or symlink) of the same name is never opened;
+/// another name is tried instead.
+fn create_temp_file() -> std::io::Result<(PathBuf, File)> {
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+    const ATTEMPTS: usize = 100;
+
+    let mut last_error = None;
+    for _ in 0..ATTEMPTS {
+        let path = std::env::temp_dir().join(format!(
+            "kytea-tokenizer-{}-{}.txt",
+            std::process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed),
+        ));
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => last_error = Some(e),
+            Err(e) => return Err(e),
+        }
+    }
+    Err(last_error.expect("at least one file was tried"))
+}
+
+impl Drop for TempCorpora {
+    fn drop(&mut self) {
+        for (path, _) in &self.files {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+fn write_full(w: &mut impl Write, words: &[Word]) -> std::io::Result<()> {
+    for (i, word) in words.iter().enumerate() {
+        if i > 0 {
+            w.write_all(&[FULL_WORD_DELIM])?;
+        }
+        write_word(w, &word.surface, &word.tags)?;
+    }
+    w.write_all(b"\n")
+}
+
+/// Writes `text` in the partial annotation format.
+///
+/// KyTea reads a line per sentence and marks between characters, so a newline or a delimiter of
+/// the format in `text` is rejected with [`ErrorKind::InvalidInput`].
+fn write_part(
+    w: &mut impl Write,
+    text: &str,
+    boundaries: &[(usize, Boundary)],
+) -> std::io::Result<()> {
+    const SPECIAL: &[u8] = &[
+        HAS_BOUND, NO_BOUND, UNK_BOUND, TAG_DELIM, ELEM_DELIM, ESCAPE, b'\n',
+    ];
+
+    if let Some((i, c)) = text
+        .char_indices()
+        .find(|&(_, c)| c.is_ascii() && SPECIAL.contains(&(c as u8)))
+    {
+        return Err(std::io::Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "{:?} at {} is a delimiter of the partial annotation format",
+                c, i
+            ),
+        ));
+    }
+
+    let mut buf = [0u8; 4];
+    for (i, c) in text.char_indices() {
+        if i > 0 {
+            let mark = match boundaries.iter().find(|&&(offset, _)| offset == i) {
+                Some((_, Boundary::Bound)) => HAS_BOUND,
+                Some((_, Boundary::NoBound)) => NO_BOUND,
+                _ => UNK_BOUND,
+            };
+            w.write_all(&[mark])?;
+        }
+        w.write_all(c.encode_utf8(&mut buf).as_bytes())?;
+    }
+    w.write_all(b"\n")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::TagIterator;
+    use crate::Boundaries;
+
+    fn unescape(s: &str) -> String {
+        let mut unescaped = String::new();
+        let mut chars = s.chars();
+        while let Some(c) = chars.next() {
+            if c == char::from(ESCAPE) {
+                unescaped.extend(chars.next());
+            } else {
+                unescaped.push(c);
+            }
+        }
+        unescaped
+    }
+
+    fn word(surface: &str, tags: &[&str]) -> Word {
+        Word {
+            surface: surface.to_string(),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn write_full_round_trip() {
+        let words = [
+            word("東京", &["名詞", "とうきょう"]),
+            word("a b/c", &["記号", "", "x"]),
+            word("\\&", &[]),
+        ];
+        let mut corpus = Vec::new();
+        write_full(&mut corpus, &words).unwrap();
+        let corpus = String::from_utf8(corpus).unwrap();
+        assert_eq!(corpus, "東京/名詞/とうきょう a\\ b\\/c/記号 \\\\\\&\n");
+
+        let parsed = TagIterator::with_delim(corpus.trim_end(), FULL_WORD_DELIM)
+            .map(|w| TagIterator::from(w).map(unescape).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            parsed,
+            [
+                vec!["東京", "名詞", "とうきょう"],
+                // Tags after an empty one are dropped.
+                vec!["a b/c", "記号"],
+                vec!["\\&"],
+            ]
+        );
+    }
+
+    #[test]
+    fn write_part_round_trip() {
+        let text = "東京へ行く";
+        let boundaries = [
+            (3, Boundary::NoBound),
+            (6, Boundary::Bound),
+            (12, Boundary::Bound),
+        ];
+        let mut corpus = Vec::new();
+        write_part(&mut corpus, text, &boundaries).unwrap();
+        let corpus = String::from_utf8(corpus).unwrap();
+        assert_eq!(corpus, "東-京|へ 行|く\n");
+
+        let parsed = Boundaries::from_line(corpus.trim_end()).collect::<Vec<_>>();
+        assert_eq!(
+            parsed,
+            [
+                (3, Boundary::NoBound),
+                (6, Boundary::Bound),
+                (9, Boundary::Unknown),
+                (12, Boundary::Bound),
+            ]
+        );
+    }
+
+    #[test]
+    fn write_part_rejects_delimiters() {
+        for text in ["東京\nへ", "東京|へ", "a-b", "a b", "a/b", "a&b", "a\\b"] {
+            let mut corpus = Vec::new();
+            let e = write_part(&mut corpus, text, &[]).unwrap_err();
+            assert_eq!(e.kind(), ErrorKind::InvalidInput, "{:?}", text);
+            assert!(corpus.is_empty());
+        }
+    }
+
+    #[test]
+    fn temp_files_are_new() {
+        let (first, _) = create_temp_file().unwrap();
+        let (second, _) = create_temp_file().unwrap();
+        assert_ne!(first, second);
+        std::fs::remove_file(first).unwrap();
+        std::fs::remove_file(second).unwrap();
+    }
+}
@@ -0,0 +1,96 @@
+use super::{
+    delete_words, words_boundary, words_candidate, words_len, words_num_boundaries,
+    words_num_candidates, words_num_tags, words_span, words_surface, Encoding, Str, VoidPtr,
+};
+use crate::word::{Word, WordBoundary, WordCandidates};
+
+impl Str {
+    /// # Safety
+    /// `ptr` must point to `size` bytes that outlive `'a`.
+    pub(super) unsafe fn as_bytes<'a>(&self) -> &'a [u8] {
+        if self.size == 0 {
+            return &[];
+        }
+        std::slice::from_raw_parts(self.ptr as *const u8, self.size as usize)
+    }
+
+    /// Decodes the text written by a model in `encoding`.
+    ///
+    /// # Safety
+    /// See [`Str::as_bytes()`].
+    pub(super) unsafe fn decode(&self, encoding: Encoding) -> String {
+        encoding.decode(self.as_bytes()).into_owned()
+    }
+}
+
+/// Owner of the word list allocated by the shim.
+pub(super) struct Words {
+    void_words: VoidPtr,
+    encoding: Encoding,
+}
+
+impl Words {
+    /// # Safety
+    /// `void_words` must be a pointer returned from `run_kytea_*_words` of a model in `encoding`.
+    pub(super) unsafe fn from_raw(void_words: VoidPtr, encoding: Encoding) -> Self {
+        Self {
+            void_words,
+            encoding,
+        }
+    }
+
+    pub(super) fn to_vec(&self) -> Vec<Word> {
+        self.to_candidates().into_iter().map(Word::from).collect()
+    }
+
+    pub(super) fn to_candidates(&self) -> Vec<WordCandidates> {
+        let len = unsafe { words_len(self.void_words) };
+        (0..len).map(|i| self.word(i)).collect()
+    }
+
+    pub(super) fn to_boundaries(&self) -> Vec<WordBoundary> {
+        let len = unsafe { words_num_boundaries(self.void_words) };
+        (0..len)
+            .map(|i| {
+                let boundary = unsafe { words_boundary(self.void_words, i) };
+                WordBoundary {
+                    offset: boundary.offset,
+                    confidence: boundary.confidence,
+                }
+            })
+            .collect()
+    }
+
+    fn word(&self, i: usize) -> WordCandidates {
+        unsafe {
+            let span = words_span(self.void_words, i);
+            let num_tags = words_num_tags(self.void_words, i);
+            WordCandidates {
+                surface: words_surface(self.void_words, i).decode(self.encoding),
+                tags: (0..num_tags).map(|lev| self.candidates(i, lev)).collect(),
+                offset_from: span.begin,
+                offset_to: span.end,
+            }
+        }
+    }
+
+    fn candidates(&self, i: usize, lev: usize) -> Vec<(String, f64)> {
+        unsafe {
+            let len = words_num_candidates(self.void_words, i, lev);
+            (0..len)
+                .map(|k| {
+                    let candidate = words_candidate(self.void_words, i, lev, k);
+                    (candidate.tag.decode(self.encoding), candidate.score)
+                })
+                .collect()
+        }
+    }
+}
+
+impl Drop for Words {
+    fn drop(&mut self) {
+        unsafe {
+            delete_words(self.void_words);
+        }
+    }
+}
@@ -0,0 +1,63 @@
+//! Input and output formats of KyTea, `-in` and `-out` of the `kytea` binary.
+
+/// Output format of KyTea, `-out` of the `kytea` binary.
+///
+/// Word boundaries are tabs in every format, as in [`WordIterator`](crate::WordIterator).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum OutputFormat {
+    /// `full`: words with all tags, e.g. `コーパス/名詞/こーぱす`.
+    #[default]
+    Full,
+    /// `tok`: words only.
+    Tok,
+    /// `part`: partial annotation, read by [`Boundaries`](crate::Boundaries).
+    Part,
+    /// `conf`: tag candidates with their scores, read by [`Candidates`](crate::Candidates).
+    Conf,
+    /// `eda`: the format of the EDA dependency parser.
+    Eda,
+}
+
+impl OutputFormat {
+    /// The value of `-out`.
+    pub fn kytea_name(self) -> &'static str {
+        match self {
+            Self::Full => "full",
+            Self::Tok => "tok",
+            Self::Part => "part",
+            Self::Conf => "conf",
+            Self::Eda => "eda",
+        }
+    }
+}
+
+/// Input format of the `kytea` binary, `-in`.
+///
+/// Annotated input is read with the same delimiters as the output, so words are delimited by
+/// tabs.
+#[cfg(feature = "cmd")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum InputFormat {
+    /// `raw`: plain text, one sentence per line.
+    #[default]
+    Raw,
+    /// `full`: segmented and tagged words, whose tags are kept.
+    Full,
+    /// `part`: partially annotated text, whose annotated gaps are kept.
+    Part,
+    /// `conf`: the output of `-out conf`.
+    Conf,
+}
+
+#[cfg(feature = "cmd")]
+impl InputFormat {
+    /// The value of `-in`.
+    pub fn kytea_name(self) -> &'static str {
+        match self {
+            Self::Raw => "raw",
+            Self::Full => "full",
+            Self::Part => "part",
+            Self::Conf => "conf",
+        }
+    }
+}
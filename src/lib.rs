@@ -5,6 +5,11 @@ pub const WORD_DELIM: u8 = b'\t';
 const DELIM_STR: &str = "\t";
 pub const ESCAPE: u8 = b'\\';
 pub const TAG_DELIM: u8 = b'/';
+pub const ELEM_DELIM: u8 = b'&';
+pub const HAS_BOUND: u8 = b'|';
+pub const NO_BOUND: u8 = b'-';
+pub const UNK_BOUND: u8 = b' ';
+pub const SKIP_BOUND: u8 = b'?';
 
 #[cfg(feature = "cmd")]
 mod cmd;
@@ -12,15 +17,54 @@ mod cmd;
 pub use cmd::kytea_command as cmd;
 #[cfg(feature = "cmd")]
 pub use cmd::run_cmd;
+#[cfg(feature = "cmd")]
+pub use cmd::{
+    CmdError, InputFormat, KyteaCommandBuilder, KyteaProcess, OptionError, OutputFormat,
+};
 
 #[cfg(feature = "ffi")]
 pub mod ffi;
 
+#[cfg(any(feature = "ffi", feature = "cmd", feature = "model"))]
+pub mod encoding;
+#[cfg(any(feature = "ffi", feature = "cmd", feature = "model"))]
+pub use encoding::Encoding;
+
+#[cfg(feature = "model")]
+pub mod model;
+
+#[cfg(feature = "native")]
+pub mod native;
+
+#[cfg(feature = "mock")]
+pub mod mock;
+
+#[cfg(any(feature = "native", feature = "mock"))]
+mod chars;
+
+#[cfg(any(feature = "ffi", feature = "cmd", feature = "native", feature = "mock"))]
+mod word;
+#[cfg(any(feature = "ffi", feature = "cmd", feature = "native", feature = "mock"))]
+pub use word::{Sentence, Tokenize, Word, WordBoundary, WordCandidates};
+
+#[cfg(any(feature = "ffi", feature = "cmd"))]
+mod format;
+
+#[cfg(any(feature = "ffi", feature = "cmd"))]
+pub mod resolver;
+#[cfg(any(feature = "ffi", feature = "cmd"))]
+pub use resolver::ModelResolver;
+
+pub mod dictionary;
+
 mod pos;
 pub use pos::PoS;
 pub use pos::PosIterator;
 
 mod parser;
+pub use parser::Boundaries;
+pub use parser::Boundary;
+pub use parser::Candidates;
 pub use parser::DefaultTags;
 pub use parser::Surface;
 pub use parser::Tags;
@@ -0,0 +1,267 @@
+//! A deterministic tokenizer with KyTea's output, for testing code that uses a model without
+//! libkytea nor a model file.
+//!
+//! [`Model`] splits each line into
+//!
+//! 1. the longest word of its dictionary starting at a character, if any;
+//! 2. otherwise, the run of characters of the same type (kanji, hiragana, katakana, digits or
+//!    latin letters) up to the next dictionary word;
+//! 3. otherwise, each symbol or space by itself.
+//!
+//! Dictionary words are tagged with their tags and the others with [`Model::unknown_tags()`].
+//!
+//! ```
+//! use kytea_tokenizer::mock::Model;
+//! use kytea_tokenizer::{Surface, WordIterator};
+//!
+//! let model = Model::new()
+//!     .word("へ", ["助詞"])
+//!     .word("行く", ["動詞"])
+//!     .unknown_tags(["名詞"]);
+//! let output = model.tokenize("東京へ行く");
+//! assert_eq!(output, "東京/名詞\tへ/助詞\t行く/動詞\n");
+//!
+//! let surfaces = WordIterator::<(Surface, ())>::from_lines(&output)
+//!     .map(|(surface, _)| surface.0)
+//!     .collect::<Vec<_>>();
+//! assert_eq!(surfaces, ["東京", "へ", "行く"]);
+//! ```
+
+use std::collections::HashMap;
+
+use crate::chars::{char_type, normalize};
+use crate::dictionary::DictionaryEntry;
+use crate::word::{lines, write_words};
+use crate::{Tokenize, Word, WordBoundary};
+
+/// Character type of symbols, spaces and the others, which are words by themselves.
+const OTHER: char = 'O';
+
+/// A tokenizer with an in-memory dictionary, configured by chaining.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Model {
+    words: HashMap<String, Vec<String>>,
+    /// The length of the longest word of the dictionary, in characters.
+    max_word_len: usize,
+    unknown_tags: Vec<String>,
+}
+
+impl Model {
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds a word to the dictionary with the tags of each tag level, replacing its tags if it
+    /// is already there.
+    pub fn word(
+        mut self,
+        surface: impl Into<String>,
+        tags: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        let surface = surface.into();
+        self.max_word_len = self.max_word_len.max(surface.chars().count());
+        self.words
+            .insert(surface, tags.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Adds the entries to the dictionary, e.g. the ones written by
+    /// [`write_dictionary()`](crate::dictionary::write_dictionary) for the real model.
+    pub fn entries(mut self, entries: impl IntoIterator<Item = DictionaryEntry>) -> Self {
+        for entry in entries {
+            self = self.word(entry.surface, entry.tags);
+        }
+        self
+    }
+
+    /// Tags of the words not in the dictionary; none by default.
+    pub fn unknown_tags(mut self, tags: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.unknown_tags = tags.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// The number of tag levels, i.e. the most tags of a word.
+    pub fn num_tags(&self) -> usize {
+        self.words
+            .values()
+            .map(Vec::len)
+            .chain(Some(self.unknown_tags.len()))
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Tokenizes `text` and returns the output in the format read by
+    /// [`WordIterator`](crate::WordIterator), as KyTea writes it.
+    pub fn tokenize(&self, text: &str) -> String {
+        let mut output = String::with_capacity(text.len() * 2);
+        for (offset, line) in lines(text) {
+            write_words(&mut output, &self.line_words(line, offset));
+        }
+        output
+    }
+
+    /// Tokenizes `text` into words; offsets are byte indices in `text`.
+    pub fn tokenize_words(&self, text: &str) -> Vec<Word> {
+        lines(text)
+            .flat_map(|(offset, line)| self.line_words(line, offset))
+            .collect()
+    }
+
+    /// Every gap between two characters in a line, with the confidence `1.0` on word
+    /// boundaries and `-1.0` elsewhere.
+    pub fn tokenize_to_boundaries(&self, text: &str) -> Vec<WordBoundary> {
+        lines(text)
+            .flat_map(|(offset, line)| {
+                let words = self.line_words(line, offset);
+                line.char_indices()
+                    .skip(1)
+                    .map(|(i, _)| {
+                        let gap = offset + i;
+                        let is_boundary = words.iter().any(|word| word.offset_from == gap);
+                        WordBoundary {
+                            offset: gap,
+                            confidence: if is_boundary { 1.0 } else { -1.0 },
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    fn line_words(&self, line: &str, offset: usize) -> Vec<Word> {
+        let mut offsets = line.char_indices().map(|(i, _)| i).collect::<Vec<_>>();
+        offsets.push(line.len());
+        let types = line
+            .chars()
+            .map(|c| char_type(normalize(c)))
+            .collect::<Vec<_>>();
+        let num_tags = self.num_tags();
+
+        let mut words = Vec::new();
+        let mut start = 0;
+        while start < types.len() {
+            let (end, tags) = match self.longest_word(line, &offsets, start) {
+                Some((end, tags)) => (end, tags),
+                None => {
+                    let mut end = start + 1;
+                    while types[start] != OTHER
+                        && end < types.len()
+                        && types[end] == types[start]
+                        && self.longest_word(line, &offsets, end).is_none()
+                    {
+                        end += 1;
+                    }
+                    (end, &self.unknown_tags)
+                }
+            };
+            let mut tags = tags.clone();
+            tags.resize(num_tags, String::new());
+            words.push(Word {
+                surface: line[offsets[start]..offsets[end]].to_string(),
+                tags,
+                offset_from: offset + offsets[start],
+                offset_to: offset + offsets[end],
+            });
+            start = end;
+        }
+        words
+    }
+
+    /// The end (in characters) and the tags of the longest dictionary word at `start`.
+    fn longest_word(
+        &self,
+        line: &str,
+        offsets: &[usize],
+        start: usize,
+    ) -> Option<(usize, &Vec<String>)> {
+        let max_end = (start + self.max_word_len).min(offsets.len() - 1);
+        (start + 1..=max_end).rev().find_map(|end| {
+            self.words
+                .get(&line[offsets[start]..offsets[end]])
+                .map(|tags| (end, tags))
+        })
+    }
+}
+
+impl Tokenize for Model {
+    type Error = std::convert::Infallible;
+
+    #[inline]
+    fn tokenize(&mut self, text: &str) -> Result<String, Self::Error> {
+        Ok(Model::tokenize(self, text))
+    }
+
+    #[inline]
+    fn tokenize_words(&mut self, text: &str) -> Result<Vec<Word>, Self::Error> {
+        Ok(Model::tokenize_words(self, text))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{PoS, Surface, WordIterator};
+
+    fn model() -> Model {
+        Model::new()
+            .word("東京", ["名詞", "とうきょう"])
+            .word("東京都", ["名詞", "とうきょうと"])
+            .word("に", ["助詞", "に"])
+            .unknown_tags(["名詞"])
+    }
+
+    #[test]
+    fn segment_by_dictionary_and_script() {
+        let model = model();
+        assert_eq!(
+            model.tokenize("東京都に住むKyTea 123個/\n東京に\n"),
+            "東京都/名詞/とうきょうと\tに/助詞/に\t住/名詞\tむ/名詞\tKyTea/名詞\t /名詞\t\
+             123/名詞\t個/名詞\t\\//名詞\n東京/名詞/とうきょう\tに/助詞/に\n"
+        );
+    }
+
+    #[test]
+    fn words_and_boundaries() {
+        let model = model();
+        let text = "x\n東京にい";
+        let words = model.tokenize_words(text);
+        assert_eq!(
+            words
+                .iter()
+                .map(|word| (word.surface.as_str(), word.offset_from, word.offset_to))
+                .collect::<Vec<_>>(),
+            [("x", 0, 1), ("東京", 2, 8), ("に", 8, 11), ("い", 11, 14)]
+        );
+        assert_eq!(words[0].tags, ["名詞", ""]);
+        assert_eq!(words[0].to_tags::<(Surface, PoS)>().1, PoS::名詞);
+
+        let boundaries = model
+            .tokenize_to_boundaries(text)
+            .into_iter()
+            .map(|b| (b.offset, b.is_boundary()))
+            .collect::<Vec<_>>();
+        assert_eq!(boundaries, [(5, false), (8, true), (11, true)]);
+    }
+
+    #[test]
+    fn parsed_by_word_iterator() {
+        let mut model = model();
+        let output = Tokenize::tokenize(&mut model, "東京に\ta/b").unwrap();
+        let words = WordIterator::<(Surface, PoS)>::from_lines(&output)
+            .map(|(surface, pos)| (surface.0, pos))
+            .collect::<Vec<_>>();
+        // Surfaces are slices of the output, still escaped.
+        assert_eq!(
+            words,
+            [
+                ("東京", PoS::名詞),
+                ("に", PoS::助詞),
+                ("\\\t", PoS::名詞),
+                ("a", PoS::名詞),
+                ("\\/", PoS::名詞),
+                ("b", PoS::名詞),
+            ]
+        );
+    }
+}
@@ -0,0 +1,90 @@
+//! The binary format: little-endian 32-bit integers, 16-bit weights and 64-bit floats, strings
+//! and lists prefixed by their length as an unsigned 32-bit integer, and the options as a list
+//! of `(name, value)` string pairs.
+
+use super::{ModelFileError, Source, Weight};
+use crate::Encoding;
+
+use std::io::{BufRead, ErrorKind, Read};
+
+pub(super) struct BinarySource<R> {
+    reader: R,
+    encoding: Encoding,
+    /// Offset of the next byte from the end of the header.
+    offset: u64,
+}
+
+impl<R: BufRead> BinarySource<R> {
+    pub(super) fn new(reader: R, encoding: Encoding) -> Self {
+        Self {
+            reader,
+            encoding,
+            offset: 0,
+        }
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], ModelFileError> {
+        let mut buf = [0u8; N];
+        match self.reader.read_exact(&mut buf) {
+            Ok(()) => {
+                self.offset += N as u64;
+                Ok(buf)
+            }
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => {
+                Err(self.error(String::from("unexpected end of file")))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn len(&mut self) -> Result<usize, ModelFileError> {
+        Ok(u32::from_le_bytes(self.array()?) as usize)
+    }
+}
+
+impl<R: BufRead> Source for BinarySource<R> {
+    fn config(&mut self) -> Result<Vec<(String, String)>, ModelFileError> {
+        let n = self.len()?;
+        (0..n)
+            .map(|_| Ok((self.string()?, self.string()?)))
+            .collect()
+    }
+
+    fn int(&mut self) -> Result<i64, ModelFileError> {
+        Ok(i32::from_le_bytes(self.array()?) as i64)
+    }
+
+    fn float(&mut self) -> Result<f64, ModelFileError> {
+        Ok(f64::from_le_bytes(self.array()?))
+    }
+
+    fn string(&mut self) -> Result<String, ModelFileError> {
+        let len = self.len()?;
+        // The length may be broken, so the buffer grows as the bytes are actually read.
+        let mut bytes = Vec::new();
+        (&mut self.reader)
+            .take(len as u64)
+            .read_to_end(&mut bytes)?;
+        self.offset += bytes.len() as u64;
+        if bytes.len() < len {
+            return Err(self.error(String::from("unexpected end of file")));
+        }
+        Ok(self.encoding.decode(&bytes).into_owned())
+    }
+
+    fn ints(&mut self) -> Result<Vec<i64>, ModelFileError> {
+        let n = self.len()?;
+        (0..n).map(|_| self.int()).collect()
+    }
+
+    fn weights(&mut self) -> Result<Vec<Weight>, ModelFileError> {
+        let n = self.len()?;
+        (0..n)
+            .map(|_| Ok(Weight::from_le_bytes(self.array()?)))
+            .collect()
+    }
+
+    fn error(&self, msg: String) -> ModelFileError {
+        ModelFileError::BadFormat(format!("byte {} after the header: {}", self.offset, msg))
+    }
+}
@@ -0,0 +1,724 @@
+//! Reader of KyTea model files which does not need libkytea.
+//!
+//! Both formats written by `train-kytea` are read: the text format (`-modtext`) and the binary
+//! one. After the header line `KyTea <version> <T|B> <encoding>`, a model file consists of:
+//!
+//! 1. the training options, e.g. `-charw 3`;
+//! 2. the word segmentation model;
+//! 3. the dictionary, with the local tagging models of each word;
+//! 4. the subword dictionary used to guess the tags of unknown words;
+//! 5. the global tagging model of each tag level with its tags;
+//! 6. the subword language model of each tag level.
+//!
+//! Each classifier keeps integer weights and the multiplier scaling them, mostly in the
+//! [`FeatureLookup`] built by `train-kytea`.
+//!
+//! The two formats differ only in how numbers, strings and lists are written; see the
+//! `text` and `binary` modules. Strings are decoded from the encoding of the model.
+
+use crate::Encoding;
+
+use std::fmt;
+use std::io::BufRead;
+use std::path::Path;
+
+#[cfg(feature = "json")]
+use serde::{Deserialize, Serialize};
+
+mod binary;
+mod text;
+
+/// Versions of KyTea whose model files can be read.
+const SUPPORTED_VERSION: &str = "0.4.";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub enum FileFormat {
+    Text,
+    Binary,
+}
+
+/// A KyTea model read from a file.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct KyteaModel {
+    /// Version of KyTea which wrote the model, e.g. `0.4.7`.
+    pub version: String,
+    pub format: FileFormat,
+    pub encoding: Encoding,
+    pub config: Config,
+    /// The word segmentation model; `None` if the model does not segment words.
+    pub ws_model: Option<LinearModel>,
+    pub dictionary: Vec<DictionaryWord>,
+    pub subword_dictionary: Vec<SubwordEntry>,
+    /// Models of each tag level, e.g. the PoS and the pronunciation.
+    pub tag_levels: Vec<TagLevel>,
+}
+
+/// Training options of the model, in the order they are written.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct Config {
+    /// `(name, value)` pairs, the name without the leading `-`, e.g. `("charw", "3")`.
+    pub options: Vec<(String, String)>,
+}
+
+impl Config {
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.options
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// `-numtags`: the number of tag levels.
+    pub fn num_tags(&self) -> Option<usize> {
+        self.get("numtags")?.parse().ok()
+    }
+
+    /// `-charw`: the window of character n-gram features.
+    pub fn char_window(&self) -> Option<u32> {
+        self.get("charw")?.parse().ok()
+    }
+
+    /// `-charn`: the maximum length of character n-gram features.
+    pub fn char_n(&self) -> Option<u32> {
+        self.get("charn")?.parse().ok()
+    }
+
+    /// `-typew`: the window of character type n-gram features.
+    pub fn type_window(&self) -> Option<u32> {
+        self.get("typew")?.parse().ok()
+    }
+
+    /// `-typen`: the maximum length of character type n-gram features.
+    pub fn type_n(&self) -> Option<u32> {
+        self.get("typen")?.parse().ok()
+    }
+
+    /// `-dicn`: words longer than this share the same dictionary features.
+    pub fn dictionary_n(&self) -> Option<u32> {
+        self.get("dicn")?.parse().ok()
+    }
+}
+
+/// A weight of a [`LinearModel`], stored as an integer (`FeatVal` of KyTea).
+pub type Weight = i16;
+
+/// A linear classifier trained by liblinear.
+///
+/// KyTea scales the trained weights into [`Weight`] integers; the score of a class is the sum of
+/// the integer weights of the features times [`LinearModel::multiplier`].
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct LinearModel {
+    /// The classes, e.g. `[1, -1]` for word boundaries.
+    pub labels: Vec<i32>,
+    /// The liblinear solver type, as `-solver` of `train-kytea`.
+    pub solver: i32,
+    /// The value of liblinear's bias feature, negative if the model has none; its weights are
+    /// [`FeatureLookup::biases`].
+    pub bias: f64,
+    /// The value of a unit of the integer weights.
+    pub multiplier: f64,
+    /// Weights of the features by name, [`LinearModel::num_weights()`] each; empty once KyTea
+    /// has moved them into the [`FeatureLookup`].
+    pub features: Vec<(String, Vec<Weight>)>,
+    pub lookup: Option<FeatureLookup>,
+}
+
+impl LinearModel {
+    /// The number of weights of each feature.
+    #[inline]
+    pub fn num_weights(&self) -> usize {
+        if self.labels.len() == 2 {
+            1
+        } else {
+            self.labels.len()
+        }
+    }
+}
+
+/// The weights of a [`LinearModel`] indexed by what the features look at, which is how KyTea
+/// stores a trained model.
+///
+/// Each vector holds [`LinearModel::num_weights()`] consecutive weights per position.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct FeatureLookup {
+    /// Weights of each character n-gram at each position of the window, from the leftmost.
+    pub char_ngrams: Vec<(String, Vec<Weight>)>,
+    /// Same as `char_ngrams` for the character types.
+    pub type_ngrams: Vec<(String, Vec<Weight>)>,
+    /// Weights of a word being tagged, for the tagging models.
+    pub self_words: Vec<(String, Vec<Weight>)>,
+    /// Weights of the dictionary words around a gap, by dictionary, then left, inside and right
+    /// of the gap, then length up to `-dicn`.
+    pub dictionary: Vec<Weight>,
+    pub biases: Vec<Weight>,
+    /// Weights of a tagged word being in each dictionary.
+    pub tag_dictionary: Vec<Weight>,
+    /// Weights of a tagged word being in no dictionary.
+    pub tag_unknown: Vec<Weight>,
+}
+
+/// A word in the dictionary of the model.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct DictionaryWord {
+    pub surface: String,
+    /// The tags the word can take, of each tag level.
+    pub tags: Vec<Vec<String>>,
+    /// Bit `i` is set if the word is in the `i`th dictionary, the training corpus being bit 0.
+    pub in_dictionaries: u32,
+    /// The local tagging model of each tag level, if the word has more than one tag.
+    pub tag_models: Vec<Option<LinearModel>>,
+}
+
+/// A subword with the probabilities of its tags, used for unknown words.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct SubwordEntry {
+    pub surface: String,
+    /// `(tag, probability)` pairs of each tag level.
+    pub tags: Vec<Vec<(String, f64)>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct TagLevel {
+    /// The tags of the global model, in the order of its labels.
+    pub tags: Vec<String>,
+    /// The model tagging the words not in the dictionary.
+    pub model: Option<LinearModel>,
+    pub language_model: Option<LanguageModel>,
+}
+
+/// A character n-gram model of the tags of unknown words.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct LanguageModel {
+    pub n: u32,
+    pub vocabulary_size: u32,
+    /// Log probability of each n-gram.
+    pub probs: Vec<(String, f64)>,
+    /// Backoff weight of each n-gram.
+    pub fallbacks: Vec<(String, f64)>,
+}
+
+impl KyteaModel {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, ModelFileError> {
+        let file = std::fs::File::open(path)?;
+        Self::read(std::io::BufReader::new(file))
+    }
+
+    pub fn read(mut reader: impl BufRead) -> Result<Self, ModelFileError> {
+        let mut header = Vec::new();
+        reader.read_until(b'\n', &mut header)?;
+        let header = String::from_utf8_lossy(&header);
+        let fields = header.split_whitespace().collect::<Vec<_>>();
+        let (version, format, encoding) = match fields[..] {
+            ["KyTea", version, format, encoding] => (version, format, encoding),
+            _ => {
+                return Err(ModelFileError::BadFormat(format!(
+                    "bad header: {:?}",
+                    header.trim_end()
+                )))
+            }
+        };
+        if !version.starts_with(SUPPORTED_VERSION) {
+            return Err(ModelFileError::UnsupportedVersion(version.to_string()));
+        }
+        let encoding = Encoding::from_kytea_name(encoding);
+
+        let format = match format {
+            "T" => FileFormat::Text,
+            "B" => FileFormat::Binary,
+            _ => {
+                return Err(ModelFileError::BadFormat(format!(
+                    "unknown model format {:?}",
+                    format
+                )))
+            }
+        };
+
+        let mut model = match format {
+            FileFormat::Text => read_body(&mut text::TextSource::new(reader, encoding)),
+            FileFormat::Binary => read_body(&mut binary::BinarySource::new(reader, encoding)),
+        }?;
+        model.version = version.to_string();
+        model.format = format;
+        model.encoding = encoding;
+        Ok(model)
+    }
+
+    /// The number of tag levels.
+    #[inline]
+    pub fn num_tags(&self) -> usize {
+        self.tag_levels.len()
+    }
+
+    /// Every tag of the tag level `lev` in the dictionary or the global model, sorted.
+    ///
+    /// This is a superset of `ffi::Model::tags()`, which only walks the dictionary.
+    pub fn tags(&self, lev: usize) -> Vec<&str> {
+        let dictionary = self
+            .dictionary
+            .iter()
+            .filter_map(|word| word.tags.get(lev))
+            .flatten();
+        let global = self.tag_levels.get(lev).map(|level| &level.tags);
+        let tags = dictionary
+            .chain(global.into_iter().flatten())
+            .map(String::as_str)
+            .collect::<std::collections::BTreeSet<_>>();
+        tags.into_iter().collect()
+    }
+
+    /// The number of named features and n-grams of the word segmentation model.
+    pub fn num_features(&self) -> usize {
+        self.ws_model.as_ref().map_or(0, |model| {
+            let lookup = model.lookup.as_ref().map_or(0, |lookup| {
+                lookup.char_ngrams.len() + lookup.type_ngrams.len()
+            });
+            model.features.len() + lookup
+        })
+    }
+
+    #[inline]
+    pub fn dictionary_size(&self) -> usize {
+        self.dictionary.len()
+    }
+}
+
+/// Primitive values of a model file, which the text and binary formats write differently.
+trait Source {
+    /// `(name, value)` pairs of the training options.
+    fn config(&mut self) -> Result<Vec<(String, String)>, ModelFileError>;
+    fn int(&mut self) -> Result<i64, ModelFileError>;
+    fn float(&mut self) -> Result<f64, ModelFileError>;
+    fn string(&mut self) -> Result<String, ModelFileError>;
+    fn ints(&mut self) -> Result<Vec<i64>, ModelFileError>;
+    /// A vector of integer weights (`FeatVec` of KyTea).
+    fn weights(&mut self) -> Result<Vec<Weight>, ModelFileError>;
+    /// An error at the current position.
+    fn error(&self, msg: String) -> ModelFileError;
+
+    /// A non-negative integer, e.g. the length of a list.
+    fn count(&mut self) -> Result<usize, ModelFileError> {
+        let n = self.int()?;
+        usize::try_from(n).map_err(|_| self.error(format!("negative count {}", n)))
+    }
+
+    fn list<T>(
+        &mut self,
+        mut item: impl FnMut(&mut Self) -> Result<T, ModelFileError>,
+    ) -> Result<Vec<T>, ModelFileError> {
+        let n = self.count()?;
+        // The count may be broken, so it is not trusted for the allocation.
+        let mut items = Vec::with_capacity(n.min(1024));
+        for _ in 0..n {
+            items.push(item(self)?);
+        }
+        Ok(items)
+    }
+}
+
+fn read_body(src: &mut impl Source) -> Result<KyteaModel, ModelFileError> {
+    let config = Config {
+        options: src.config()?,
+    };
+    let num_tags = config
+        .num_tags()
+        .ok_or_else(|| src.error(String::from("the options have no valid -numtags")))?;
+
+    let ws_model = read_linear_model(src)?;
+    let dictionary = src.list(|src| {
+        Ok(DictionaryWord {
+            surface: src.string()?,
+            in_dictionaries: src.count()? as u32,
+            tags: (0..num_tags)
+                .map(|_| src.list(Source::string))
+                .collect::<Result<_, _>>()?,
+            tag_models: (0..num_tags)
+                .map(|_| read_linear_model(src))
+                .collect::<Result<_, _>>()?,
+        })
+    })?;
+    let subword_dictionary = src.list(|src| {
+        Ok(SubwordEntry {
+            surface: src.string()?,
+            tags: (0..num_tags)
+                .map(|_| src.list(|src| Ok((src.string()?, src.float()?))))
+                .collect::<Result<_, _>>()?,
+        })
+    })?;
+
+    let mut tag_levels = Vec::with_capacity(num_tags);
+    for _ in 0..num_tags {
+        tag_levels.push(TagLevel {
+            tags: src.list(Source::string)?,
+            model: read_linear_model(src)?,
+            language_model: None,
+        });
+    }
+    for level in &mut tag_levels {
+        level.language_model = read_language_model(src)?;
+    }
+
+    Ok(KyteaModel {
+        version: String::new(),
+        format: FileFormat::Text,
+        encoding: Encoding::Utf8,
+        config,
+        ws_model,
+        dictionary,
+        subword_dictionary,
+        tag_levels,
+    })
+}
+
+/// Reads a model, or `None` if it has no labels.
+fn read_linear_model(src: &mut impl Source) -> Result<Option<LinearModel>, ModelFileError> {
+    let labels = src.ints()?;
+    if labels.is_empty() {
+        return Ok(None);
+    }
+    let labels = labels
+        .into_iter()
+        .map(|label| i32::try_from(label).map_err(|_| src.error(format!("bad label {}", label))))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut model = LinearModel {
+        labels,
+        solver: src.int()? as i32,
+        bias: src.float()?,
+        multiplier: src.float()?,
+        features: Vec::new(),
+        lookup: None,
+    };
+    let num_weights = model.num_weights();
+    model.features = src.list(|src| {
+        let name = src.string()?;
+        let weights = src.weights()?;
+        if weights.len() != num_weights {
+            return Err(src.error(format!(
+                "feature {:?} has {} weights, expected {}",
+                name,
+                weights.len(),
+                num_weights
+            )));
+        }
+        Ok((name, weights))
+    })?;
+    model.lookup = read_feature_lookup(src, num_weights)?;
+    Ok(Some(model))
+}
+
+/// Reads the feature lookup of a model, or `None` if its flag is 0.
+fn read_feature_lookup(
+    src: &mut impl Source,
+    num_weights: usize,
+) -> Result<Option<FeatureLookup>, ModelFileError> {
+    if src.count()? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(FeatureLookup {
+        char_ngrams: read_vector_dictionary(src, num_weights)?,
+        type_ngrams: read_vector_dictionary(src, num_weights)?,
+        self_words: read_vector_dictionary(src, num_weights)?,
+        dictionary: read_vector(src, num_weights)?,
+        biases: read_vector(src, num_weights)?,
+        tag_dictionary: read_vector(src, num_weights)?,
+        tag_unknown: read_vector(src, num_weights)?,
+    }))
+}
+
+/// Reads `(key, weights)` pairs, e.g. the weights of each character n-gram.
+fn read_vector_dictionary(
+    src: &mut impl Source,
+    num_weights: usize,
+) -> Result<Vec<(String, Vec<Weight>)>, ModelFileError> {
+    src.list(|src| Ok((src.string()?, read_vector(src, num_weights)?)))
+}
+
+/// Reads weights of one or more positions of `num_weights` weights each.
+fn read_vector(src: &mut impl Source, num_weights: usize) -> Result<Vec<Weight>, ModelFileError> {
+    let weights = src.weights()?;
+    if weights.len() % num_weights != 0 {
+        return Err(src.error(format!(
+            "{} weights are not a multiple of {}",
+            weights.len(),
+            num_weights
+        )));
+    }
+    Ok(weights)
+}
+
+/// Reads a language model, or `None` if its `n` is 0.
+fn read_language_model(src: &mut impl Source) -> Result<Option<LanguageModel>, ModelFileError> {
+    let n = src.count()?;
+    if n == 0 {
+        return Ok(None);
+    }
+    Ok(Some(LanguageModel {
+        n: n as u32,
+        vocabulary_size: src.count()? as u32,
+        probs: src.list(|src| Ok((src.string()?, src.float()?)))?,
+        fallbacks: src.list(|src| Ok((src.string()?, src.float()?)))?,
+    }))
+}
+
+/// An error while reading a model file.
+#[derive(Debug)]
+pub enum ModelFileError {
+    Io(std::io::Error),
+    /// The file is not a KyTea model or is broken; the message tells where.
+    BadFormat(String),
+    /// The model was written by an unsupported version of KyTea.
+    UnsupportedVersion(String),
+}
+
+impl fmt::Display for ModelFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "I/O error: {}", e),
+            Self::BadFormat(msg) => write!(f, "bad model format: {}", msg),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported model version: {}", version)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ModelFileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for ModelFileError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const TEXT: &str = "KyTea 0.4.7 T utf8
+-numtags 1
+-charw 3
+
+1 -1
+1
+1
+0.5
+1
+X-1あ
+3
+1
+1
+あ
+1 2 3 4 5 6
+0
+0
+1 -1
+-2
+
+
+1
+東京
+1
+1
+名詞
+
+1
+ア
+1
+名詞
+0.75
+2
+名詞
+動詞
+1 2 3
+4
+-1
+0.5
+1
+B
+1 2 3
+0
+3
+4
+1
+a
+-1.5
+0
+";
+
+    struct Binary(Vec<u8>);
+
+    impl Binary {
+        fn int(mut self, n: i32) -> Self {
+            self.0.extend_from_slice(&n.to_le_bytes());
+            self
+        }
+
+        fn float(mut self, x: f64) -> Self {
+            self.0.extend_from_slice(&x.to_le_bytes());
+            self
+        }
+
+        fn string(mut self, s: &str) -> Self {
+            self = self.int(s.len() as i32);
+            self.0.extend_from_slice(s.as_bytes());
+            self
+        }
+
+        fn weights(mut self, weights: &[Weight]) -> Self {
+            self = self.int(weights.len() as i32);
+            for w in weights {
+                self.0.extend_from_slice(&w.to_le_bytes());
+            }
+            self
+        }
+    }
+
+    #[test]
+    fn read_text_and_binary() {
+        let text = KyteaModel::read(TEXT.as_bytes()).unwrap();
+
+        let binary = Binary(b"KyTea 0.4.7 B utf8\n".to_vec())
+            // options
+            .int(2)
+            .string("numtags")
+            .string("1")
+            .string("charw")
+            .string("3")
+            // word segmentation
+            .int(2)
+            .int(1)
+            .int(-1)
+            .int(1)
+            .float(1.0)
+            .float(0.5)
+            .int(1)
+            .string("X-1あ")
+            .weights(&[3])
+            .int(1)
+            .int(1)
+            .string("あ")
+            .weights(&[1, 2, 3, 4, 5, 6])
+            .int(0)
+            .int(0)
+            .weights(&[1, -1])
+            .weights(&[-2])
+            .weights(&[])
+            .weights(&[])
+            // dictionary
+            .int(1)
+            .string("東京")
+            .int(1)
+            .int(1)
+            .string("名詞")
+            .int(0)
+            // subword dictionary
+            .int(1)
+            .string("ア")
+            .int(1)
+            .string("名詞")
+            .float(0.75)
+            // global tags and model
+            .int(2)
+            .string("名詞")
+            .string("動詞")
+            .int(3)
+            .int(1)
+            .int(2)
+            .int(3)
+            .int(4)
+            .float(-1.0)
+            .float(0.5)
+            .int(1)
+            .string("B")
+            .weights(&[1, 2, 3])
+            .int(0)
+            // language model
+            .int(3)
+            .int(4)
+            .int(1)
+            .string("a")
+            .float(-1.5)
+            .int(0);
+        let binary = KyteaModel::read(binary.0.as_slice()).unwrap();
+
+        assert_eq!(text.version, "0.4.7");
+        assert_eq!(text.format, FileFormat::Text);
+        assert_eq!(binary.format, FileFormat::Binary);
+        assert_eq!(
+            KyteaModel {
+                format: FileFormat::Text,
+                ..binary
+            },
+            text
+        );
+
+        assert_eq!(text.config.char_window(), Some(3));
+        assert_eq!(text.num_tags(), 1);
+        assert_eq!(text.num_features(), 2);
+        assert_eq!(text.tags(0), ["動詞", "名詞"]);
+        let ws = text.ws_model.as_ref().unwrap();
+        assert_eq!(ws.features[0], (String::from("X-1あ"), vec![3]));
+        let lookup = ws.lookup.as_ref().unwrap();
+        assert_eq!(lookup.char_ngrams[0].1, [1, 2, 3, 4, 5, 6]);
+        assert_eq!(
+            (&*lookup.dictionary, &*lookup.biases),
+            (&[1, -1][..], &[-2][..])
+        );
+        assert!(lookup.tag_unknown.is_empty());
+        assert_eq!(text.dictionary[0].tags, [["名詞"]]);
+        assert_eq!(text.dictionary[0].tag_models, [None]);
+        let global = text.tag_levels[0].model.as_ref().unwrap();
+        assert_eq!(global.features[0].1, [1, 2, 3]);
+        assert_eq!(global.lookup, None);
+        let lm = text.tag_levels[0].language_model.as_ref().unwrap();
+        assert_eq!((lm.n, lm.vocabulary_size), (3, 4));
+    }
+
+    #[test]
+    fn read_errors() {
+        let err = KyteaModel::read("KyTea 0.3.0 T utf8\n".as_bytes()).unwrap_err();
+        assert!(matches!(err, ModelFileError::UnsupportedVersion(_)));
+
+        let err = KyteaModel::read("not a model\n".as_bytes()).unwrap_err();
+        assert!(matches!(err, ModelFileError::BadFormat(_)));
+
+        let broken = TEXT.replace("X-1あ\n3\n", "X-1あ\n3 1\n");
+        let err = KyteaModel::read(broken.as_bytes()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "bad model format: line 11: feature \"X-1あ\" has 2 weights, expected 1"
+        );
+
+        // Weights are integers, which KyTea scales by the multiplier.
+        let broken = TEXT.replace("X-1あ\n3\n", "X-1あ\n0.25\n");
+        let err = KyteaModel::read(broken.as_bytes()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "bad model format: line 11: \"0.25\" is not a number"
+        );
+
+        let broken = TEXT.replace("1 2 3\n0\n3", "1 2 3\n1\n0\n0\n0\n1 2\n\n\n\n3");
+        let err = KyteaModel::read(broken.as_bytes()).unwrap_err();
+        assert!(err.to_string().contains("not a multiple of 3"), "{}", err);
+
+        let truncated = &TEXT[..TEXT.find("東京").unwrap()];
+        let err = KyteaModel::read(truncated.as_bytes()).unwrap_err();
+        assert!(err.to_string().contains("unexpected end of file"));
+    }
+}
@@ -0,0 +1,96 @@
+//! The text format: one value per line, lists of numbers (labels and weights) on a single line
+//! separated by spaces, and the options as `-name value` lines ended by an empty line.
+
+use super::{ModelFileError, Source, Weight};
+use crate::Encoding;
+
+use std::io::BufRead;
+use std::str::FromStr;
+
+pub(super) struct TextSource<R> {
+    reader: R,
+    encoding: Encoding,
+    line: Vec<u8>,
+    /// 1-based number of the last line read; the header is line 1.
+    line_no: usize,
+}
+
+impl<R: BufRead> TextSource<R> {
+    pub(super) fn new(reader: R, encoding: Encoding) -> Self {
+        Self {
+            reader,
+            encoding,
+            line: Vec::new(),
+            line_no: 1,
+        }
+    }
+
+    fn next_line(&mut self) -> Result<&[u8], ModelFileError> {
+        self.line.clear();
+        self.line_no += 1;
+        if self.reader.read_until(b'\n', &mut self.line)? == 0 {
+            return Err(self.error(String::from("unexpected end of file")));
+        }
+        if self.line.last() == Some(&b'\n') {
+            self.line.pop();
+        }
+        Ok(&self.line)
+    }
+
+    fn parse<T: FromStr>(&self, s: &str) -> Result<T, ModelFileError> {
+        s.parse()
+            .map_err(|_| self.error(format!("{:?} is not a number", s)))
+    }
+
+    fn parse_line<T: FromStr>(&mut self) -> Result<Vec<T>, ModelFileError> {
+        self.next_line()?;
+        let line = String::from_utf8_lossy(&self.line);
+        line.split_ascii_whitespace()
+            .map(|s| self.parse(s))
+            .collect()
+    }
+}
+
+impl<R: BufRead> Source for TextSource<R> {
+    fn config(&mut self) -> Result<Vec<(String, String)>, ModelFileError> {
+        let mut options = Vec::new();
+        loop {
+            let line = String::from_utf8_lossy(self.next_line()?).into_owned();
+            if line.is_empty() {
+                return Ok(options);
+            }
+            let option = line
+                .strip_prefix('-')
+                .ok_or_else(|| self.error(format!("{:?} is not an option", line)))?;
+            let (name, value) = option.split_once(' ').unwrap_or((option, ""));
+            options.push((name.to_string(), value.to_string()));
+        }
+    }
+
+    fn int(&mut self) -> Result<i64, ModelFileError> {
+        let line = String::from_utf8_lossy(self.next_line()?).into_owned();
+        self.parse(line.trim())
+    }
+
+    fn float(&mut self) -> Result<f64, ModelFileError> {
+        let line = String::from_utf8_lossy(self.next_line()?).into_owned();
+        self.parse(line.trim())
+    }
+
+    fn string(&mut self) -> Result<String, ModelFileError> {
+        let encoding = self.encoding;
+        Ok(encoding.decode(self.next_line()?).into_owned())
+    }
+
+    fn ints(&mut self) -> Result<Vec<i64>, ModelFileError> {
+        self.parse_line()
+    }
+
+    fn weights(&mut self) -> Result<Vec<Weight>, ModelFileError> {
+        self.parse_line()
+    }
+
+    fn error(&self, msg: String) -> ModelFileError {
+        ModelFileError::BadFormat(format!("line {}: {}", self.line_no, msg))
+    }
+}
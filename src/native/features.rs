@@ -0,0 +1,336 @@
+//! Features of KyTea's pointwise classifiers and the linear models scoring them.
+//!
+//! Features are named after what they look at, and the [`FeatureLookup`] of a model is expanded
+//! into these names when it is loaded:
+//!
+//! - `X{offset}{chars}` and `T{offset}{types}`: a character (type) n-gram starting `offset`
+//!   characters after the gap, within the window of the gap;
+//! - `D{dictionary}{L|I|R}{length}`: a word of the `dictionary`th dictionary ending at (`L`),
+//!   spanning over (`I`) or starting at (`R`) the gap, its length capped by `-dicn`;
+//! - for tagging, the same n-grams around the gap left by removing the word, `S{surface}` for
+//!   the word itself, and `TD{dictionary}` or `TU` whether it is in a dictionary or not.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::model::{FeatureLookup, LinearModel, Weight};
+
+/// Options of the features, with the defaults of `train-kytea`.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Window {
+    pub(super) char_window: usize,
+    pub(super) char_n: usize,
+    pub(super) type_window: usize,
+    pub(super) type_n: usize,
+    pub(super) dictionary_n: usize,
+}
+
+impl Default for Window {
+    fn default() -> Self {
+        Self {
+            char_window: 3,
+            char_n: 3,
+            type_window: 3,
+            type_n: 3,
+            dictionary_n: 4,
+        }
+    }
+}
+
+/// Pushes `{prefix}{offset}{ngram}` for every n-gram of at most `n` characters in
+/// `chars[from..to]`, the offset being relative to `origin`.
+fn push_ngrams(
+    features: &mut Vec<String>,
+    prefix: &str,
+    chars: &[char],
+    (from, to): (usize, usize),
+    origin: usize,
+    n: usize,
+) {
+    for start in from..to {
+        let mut feature = format!("{}{}", prefix, start as isize - origin as isize);
+        for &c in chars[start..to].iter().take(n) {
+            feature.push(c);
+            features.push(feature.clone());
+        }
+    }
+}
+
+/// Features of the gap before `chars[gap]`.
+///
+/// `words` are the dictionary words in the sentence as `(start, end, dictionaries)`.
+pub(super) fn ws_features(
+    window: &Window,
+    chars: &[char],
+    types: &[char],
+    words: &[(usize, usize, u32)],
+    gap: usize,
+) -> Vec<String> {
+    let mut features = Vec::new();
+
+    let from = gap.saturating_sub(window.char_window);
+    let to = (gap + window.char_window).min(chars.len());
+    push_ngrams(&mut features, "X", chars, (from, to), gap, window.char_n);
+
+    let from = gap.saturating_sub(window.type_window);
+    let to = (gap + window.type_window).min(types.len());
+    push_ngrams(&mut features, "T", types, (from, to), gap, window.type_n);
+
+    for &(start, end, dictionaries) in words {
+        let side = if end == gap {
+            'L'
+        } else if start == gap {
+            'R'
+        } else if start < gap && gap < end {
+            'I'
+        } else {
+            continue;
+        };
+        let len = (end - start).min(window.dictionary_n);
+        for dictionary in (0..u32::BITS).filter(|i| dictionaries & (1 << i) != 0) {
+            let mut feature = String::new();
+            let _ = write!(feature, "D{}{}{}", dictionary, side, len);
+            features.push(feature);
+        }
+    }
+    features
+}
+
+/// Features for tagging the word `chars[start..end]`, which is in the dictionaries whose bits
+/// are set in `dictionaries`.
+pub(super) fn tag_features(
+    window: &Window,
+    chars: &[char],
+    types: &[char],
+    (start, end): (usize, usize),
+    dictionaries: u32,
+) -> Vec<String> {
+    let mut features = Vec::new();
+    for (prefix, sequence, size, n) in [
+        ("X", chars, window.char_window, window.char_n),
+        ("T", types, window.type_window, window.type_n),
+    ] {
+        // The context of the word, as if the word were a gap.
+        let left = &sequence[start.saturating_sub(size)..start];
+        let right = &sequence[end..(end + size).min(sequence.len())];
+        let context = [left, right].concat();
+        push_ngrams(
+            &mut features,
+            prefix,
+            &context,
+            (0, context.len()),
+            left.len(),
+            n,
+        );
+    }
+
+    let mut surface = String::from("S");
+    surface.extend(&chars[start..end]);
+    features.push(surface);
+
+    if dictionaries == 0 {
+        features.push(String::from("TU"));
+    }
+    for dictionary in (0..u32::BITS).filter(|i| dictionaries & (1 << i) != 0) {
+        features.push(format!("TD{}", dictionary));
+    }
+    features
+}
+
+/// A [`LinearModel`] indexed by feature names.
+#[derive(Debug, Clone)]
+pub(super) struct Classifier {
+    labels: Vec<i32>,
+    multiplier: f64,
+    biases: Vec<Weight>,
+    weights: HashMap<String, Vec<Weight>>,
+}
+
+impl Classifier {
+    pub(super) fn new(model: LinearModel, window: &Window) -> Self {
+        let num_weights = model.num_weights();
+        let mut weights = model.features.into_iter().collect::<HashMap<_, _>>();
+        let mut biases = Vec::new();
+        if let Some(lookup) = model.lookup {
+            biases = lookup.biases.clone();
+            expand_lookup(&mut weights, lookup, window, num_weights);
+        }
+        biases.resize(num_weights, 0);
+        Self {
+            labels: model.labels,
+            multiplier: model.multiplier,
+            biases,
+            weights,
+        }
+    }
+
+    /// The score of each weight vector; unknown features are ignored.
+    fn scores(&self, features: &[String]) -> Vec<f64> {
+        let mut sums = self
+            .biases
+            .iter()
+            .map(|&b| i32::from(b))
+            .collect::<Vec<_>>();
+        for weights in features.iter().filter_map(|f| self.weights.get(f)) {
+            for (sum, &weight) in sums.iter_mut().zip(weights) {
+                *sum += i32::from(weight);
+            }
+        }
+        sums.into_iter()
+            .map(|sum| f64::from(sum) * self.multiplier)
+            .collect()
+    }
+
+    /// The margin of a binary classifier, positive for the first label.
+    pub(super) fn margin(&self, features: &[String]) -> f64 {
+        self.scores(features)[0]
+    }
+
+    /// Whether the first label of a binary classifier is `1`, i.e. a positive margin means yes.
+    pub(super) fn first_is_positive(&self) -> bool {
+        self.labels.first() == Some(&1)
+    }
+
+    /// The label with the best score.
+    pub(super) fn classify(&self, features: &[String]) -> i32 {
+        let scores = self.scores(features);
+        if self.labels.len() == 2 {
+            return if scores[0] > 0.0 {
+                self.labels[0]
+            } else {
+                self.labels[1]
+            };
+        }
+        let best = scores
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map_or(0, |(i, _)| i);
+        self.labels[best]
+    }
+}
+
+/// Names the weights of the lookup as the features of this module.
+fn expand_lookup(
+    weights: &mut HashMap<String, Vec<Weight>>,
+    lookup: FeatureLookup,
+    window: &Window,
+    num_weights: usize,
+) {
+    // The vectors of n-grams hold the weights of each offset in the window, from the leftmost.
+    for (prefix, ngrams, size) in [
+        ("X", lookup.char_ngrams, window.char_window),
+        ("T", lookup.type_ngrams, window.type_window),
+    ] {
+        for (ngram, vector) in ngrams {
+            for (i, position) in vector.chunks(num_weights).enumerate() {
+                let name = format!("{}{}{}", prefix, i as isize - size as isize, ngram);
+                weights.insert(name, position.to_vec());
+            }
+        }
+    }
+
+    let dictionary_n = window.dictionary_n.max(1);
+    for (i, position) in lookup.dictionary.chunks(num_weights).enumerate() {
+        let side = ['L', 'I', 'R'][(i / dictionary_n) % 3];
+        let name = format!(
+            "D{}{}{}",
+            i / (3 * dictionary_n),
+            side,
+            i % dictionary_n + 1
+        );
+        weights.insert(name, position.to_vec());
+    }
+
+    for (surface, vector) in lookup.self_words {
+        weights.insert(format!("S{}", surface), vector);
+    }
+    for (i, position) in lookup.tag_dictionary.chunks(num_weights).enumerate() {
+        weights.insert(format!("TD{}", i), position.to_vec());
+    }
+    if !lookup.tag_unknown.is_empty() {
+        weights.insert(String::from("TU"), lookup.tag_unknown);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn ws_features_of_gap() {
+        let chars = ['東', '京', 'へ'];
+        let types = ['k', 'k', 'H'];
+        let window = Window {
+            char_window: 1,
+            char_n: 2,
+            type_window: 1,
+            type_n: 1,
+            dictionary_n: 4,
+        };
+        let features = ws_features(&window, &chars, &types, &[(0, 2, 0b101)], 2);
+        assert_eq!(
+            features,
+            ["X-1京", "X-1京へ", "X0へ", "T-1k", "T0H", "D0L2", "D2L2"]
+        );
+    }
+
+    #[test]
+    fn tag_features_of_word() {
+        let chars = ['に', '東', '京', 'へ'];
+        let types = ['H', 'k', 'k', 'H'];
+        let window = Window {
+            char_window: 1,
+            char_n: 2,
+            type_window: 1,
+            type_n: 1,
+            dictionary_n: 4,
+        };
+        let features = tag_features(&window, &chars, &types, (1, 3), 0b10);
+        assert_eq!(
+            features,
+            ["X-1に", "X-1にへ", "X0へ", "T-1H", "T0H", "S東京", "TD1"]
+        );
+        let features = tag_features(&window, &chars, &types, (0, 1), 0);
+        assert_eq!(features, ["X0東", "T0k", "Sに", "TU"]);
+    }
+
+    #[test]
+    fn score_lookup() {
+        let window = Window {
+            char_window: 2,
+            char_n: 2,
+            type_window: 1,
+            type_n: 1,
+            dictionary_n: 2,
+        };
+        let model = LinearModel {
+            labels: vec![1, -1],
+            solver: 1,
+            bias: 1.0,
+            multiplier: 0.5,
+            features: vec![(String::from("T0k"), vec![7])],
+            lookup: Some(FeatureLookup {
+                // Offsets -2 to 1 for unigrams, -2 to 0 for bigrams.
+                char_ngrams: vec![
+                    (String::from("京"), vec![1, 2, 3, 4]),
+                    (String::from("東京"), vec![10, 20, 30]),
+                ],
+                type_ngrams: Vec::new(),
+                self_words: Vec::new(),
+                // Dictionaries 0 and 1, left, inside and right, lengths 1 and 2.
+                dictionary: vec![0, 0, 0, 100, 0, 0, 0, 0, 0, 0, 0, 200],
+                biases: vec![-1],
+                tag_dictionary: Vec::new(),
+                tag_unknown: Vec::new(),
+            }),
+        };
+        let classifier = Classifier::new(model, &window);
+        let features = ["X-1京", "X-2東京", "T0k", "D0I2", "D1R2", "X5京"]
+            .map(String::from)
+            .to_vec();
+        // (2 + 10 + 7 + 100 + 200 - 1) * 0.5
+        assert_eq!(classifier.margin(&features), 159.0);
+        assert!(classifier.first_is_positive());
+    }
+}
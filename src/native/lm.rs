@@ -0,0 +1,82 @@
+//! Character n-gram language models, with which KyTea scores the tags it composes for unknown
+//! words, e.g. their pronunciations.
+
+use std::collections::HashMap;
+
+/// Marks both ends of a tag in the n-grams of the model.
+const BOUNDARY: char = '\0';
+
+#[derive(Debug, Clone)]
+pub(super) struct LanguageModel {
+    n: usize,
+    vocabulary_size: u32,
+    /// Log probability of each n-gram.
+    probs: HashMap<String, f64>,
+    /// Backoff weight of each context.
+    fallbacks: HashMap<String, f64>,
+}
+
+impl LanguageModel {
+    pub(super) fn new(model: crate::model::LanguageModel) -> Self {
+        Self {
+            n: (model.n as usize).max(1),
+            vocabulary_size: model.vocabulary_size,
+            probs: model.probs.into_iter().collect(),
+            fallbacks: model.fallbacks.into_iter().collect(),
+        }
+    }
+
+    /// Log probability of `tag`, each character and the end predicted from the `n - 1` previous
+    /// ones.
+    pub(super) fn score(&self, tag: &str) -> f64 {
+        let mut chars = vec![BOUNDARY; self.n - 1];
+        chars.extend(tag.chars());
+        chars.push(BOUNDARY);
+        chars
+            .windows(self.n)
+            .map(|ngram| self.score_last(ngram))
+            .sum()
+    }
+
+    /// Log probability of the last character of `ngram`, backing off to shorter contexts until
+    /// an n-gram of the model is found, or to the uniform distribution of the vocabulary.
+    fn score_last(&self, ngram: &[char]) -> f64 {
+        let mut score = 0.0;
+        let mut key = String::new();
+        for start in 0..ngram.len() {
+            key.clear();
+            key.extend(&ngram[start..]);
+            if let Some(prob) = self.probs.get(&key) {
+                return score + prob;
+            }
+            key.pop();
+            if let Some(fallback) = self.fallbacks.get(&key) {
+                score += fallback;
+            }
+        }
+        score - f64::from(self.vocabulary_size.max(1)).ln()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn back_off() {
+        let model = LanguageModel::new(crate::model::LanguageModel {
+            n: 2,
+            vocabulary_size: 4,
+            probs: vec![
+                (String::from("\0あ"), -0.5),
+                (String::from("い"), -1.0),
+                (String::from("\0"), -2.0),
+            ],
+            fallbacks: vec![(String::from("あ"), -0.25)],
+        });
+        // P(あ|^) P(い|あ) P($|い), where P(い|あ) and P($|い) back off to the unigrams.
+        assert!((model.score("あい") - (-0.5 - 0.25 - 1.0 - 2.0)).abs() < 1e-9);
+        // An unknown character falls back to the uniform distribution.
+        assert!((model.score("う") - (-(4f64.ln()) - 2.0)).abs() < 1e-9);
+    }
+}
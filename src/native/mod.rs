@@ -0,0 +1,510 @@
+//! KyTea's pointwise word segmentation and tagging in pure Rust.
+//!
+//! [`Model`] reads the same model files as [`ffi::Model`](crate::ffi::Model) through
+//! [`KyteaModel`], without libkytea nor a C++ toolchain. It segments and tags text as libkytea
+//! does.
+//!
+//! Unknown words are tagged by the global model of the tag level if it has one. Otherwise, as
+//! for the pronunciations, the tags of their subwords in the subword dictionary are joined, and
+//! the candidate scored best with the language model of the level is chosen.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::chars::{char_type, normalize};
+use crate::model::{KyteaModel, ModelFileError};
+use crate::word::{self, write_words};
+use crate::{Tokenize, Word, WordBoundary};
+
+mod features;
+use features::{tag_features, ws_features, Classifier, Window};
+
+mod lm;
+use lm::LanguageModel;
+
+/// The tag of an unknown word that cannot be composed from subwords, `-deftag` of KyTea.
+const DEFAULT_TAG: &str = "UNK";
+
+/// The number of partial tags kept at each character of an unknown word, `-unkbeam` of KyTea.
+const UNK_BEAM: usize = 50;
+
+/// A KyTea model loaded in Rust.
+///
+/// Unlike [`ffi::Model`](crate::ffi::Model), tokenizing does not mutate the model, so it can be
+/// shared between threads as is.
+#[derive(Debug, Clone)]
+pub struct Model {
+    window: Window,
+    ws: Option<Classifier>,
+    dictionary: Vec<DictionaryEntry>,
+    /// Index of each word in `dictionary`.
+    word_ids: HashMap<String, usize>,
+    /// The length of the longest word of the dictionary, in characters.
+    max_word_len: usize,
+    /// `(tag, probability)` pairs of each tag level of each subword.
+    subwords: HashMap<String, Vec<Vec<(String, f64)>>>,
+    /// The length of the longest subword, in characters.
+    max_subword_len: usize,
+    tag_levels: Vec<TagLevel>,
+}
+
+#[derive(Debug, Clone)]
+struct DictionaryEntry {
+    tags: Vec<Vec<String>>,
+    in_dictionaries: u32,
+    tag_models: Vec<Option<Classifier>>,
+}
+
+#[derive(Debug, Clone)]
+struct TagLevel {
+    tags: Vec<String>,
+    model: Option<Classifier>,
+    language_model: Option<LanguageModel>,
+}
+
+/// A line of the input.
+struct Line<'a> {
+    text: &'a str,
+    /// Offset of the line in the whole input.
+    offset: usize,
+    /// Offset of each character in `text`, and `text.len()`.
+    offsets: Vec<usize>,
+    chars: Vec<char>,
+    types: Vec<char>,
+}
+
+impl<'a> Line<'a> {
+    fn new(text: &'a str, offset: usize) -> Self {
+        let mut offsets = text.char_indices().map(|(i, _)| i).collect::<Vec<_>>();
+        offsets.push(text.len());
+        let chars = text.chars().map(normalize).collect::<Vec<_>>();
+        let types = chars.iter().copied().map(char_type).collect();
+        Self {
+            text,
+            offset,
+            offsets,
+            chars,
+            types,
+        }
+    }
+
+    fn surface(&self, (start, end): (usize, usize)) -> &'a str {
+        &self.text[self.offsets[start]..self.offsets[end]]
+    }
+}
+
+impl Model {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, ModelFileError> {
+        KyteaModel::open(path).map(Self::new)
+    }
+
+    pub fn new(model: KyteaModel) -> Self {
+        let config = &model.config;
+        let default = Window::default();
+        let window = Window {
+            char_window: config
+                .char_window()
+                .map_or(default.char_window, |n| n as usize),
+            char_n: config.char_n().map_or(default.char_n, |n| n as usize),
+            type_window: config
+                .type_window()
+                .map_or(default.type_window, |n| n as usize),
+            type_n: config.type_n().map_or(default.type_n, |n| n as usize),
+            dictionary_n: config
+                .dictionary_n()
+                .map_or(default.dictionary_n, |n| n as usize),
+        };
+
+        let mut word_ids = HashMap::with_capacity(model.dictionary.len());
+        let mut max_word_len = 0;
+        let dictionary = model
+            .dictionary
+            .into_iter()
+            .enumerate()
+            .map(|(i, word)| {
+                max_word_len = max_word_len.max(word.surface.chars().count());
+                word_ids.insert(word.surface, i);
+                DictionaryEntry {
+                    tags: word.tags,
+                    in_dictionaries: word.in_dictionaries,
+                    tag_models: word
+                        .tag_models
+                        .into_iter()
+                        .map(|model| model.map(|model| Classifier::new(model, &window)))
+                        .collect(),
+                }
+            })
+            .collect();
+
+        let mut max_subword_len = 0;
+        let subwords = model
+            .subword_dictionary
+            .into_iter()
+            .map(|subword| {
+                max_subword_len = max_subword_len.max(subword.surface.chars().count());
+                (subword.surface, subword.tags)
+            })
+            .collect();
+
+        Self {
+            window,
+            ws: model.ws_model.map(|model| Classifier::new(model, &window)),
+            dictionary,
+            word_ids,
+            max_word_len,
+            subwords,
+            max_subword_len,
+            tag_levels: model
+                .tag_levels
+                .into_iter()
+                .map(|level| TagLevel {
+                    tags: level.tags,
+                    model: level.model.map(|model| Classifier::new(model, &window)),
+                    language_model: level.language_model.map(LanguageModel::new),
+                })
+                .collect(),
+        }
+    }
+
+    /// The number of tag levels.
+    #[inline]
+    pub fn num_tags(&self) -> usize {
+        self.tag_levels.len()
+    }
+
+    /// Tokenizes `text` and returns the output in the same format as
+    /// [`ffi::Model::tokenize()`](crate::ffi::Model::tokenize).
+    pub fn tokenize(&self, text: &str) -> String {
+        let mut output = String::with_capacity(text.len() * 2);
+        for line in lines(text) {
+            write_words(&mut output, &self.line_words(&line));
+        }
+        output
+    }
+
+    /// Tokenizes `text` as [`ffi::Model::tokenize_words()`](crate::ffi::Model::tokenize_words)
+    /// does; offsets are byte indices in `text`.
+    pub fn tokenize_words(&self, text: &str) -> Vec<Word> {
+        lines(text)
+            .flat_map(|line| self.line_words(&line))
+            .collect()
+    }
+
+    /// The confidence of every gap between two characters in a line, as
+    /// [`ffi::Model::tokenize_to_boundaries()`](crate::ffi::Model::tokenize_to_boundaries).
+    pub fn tokenize_to_boundaries(&self, text: &str) -> Vec<WordBoundary> {
+        lines(text)
+            .flat_map(|line| {
+                let words = self.dictionary_words(&line);
+                (1..line.chars.len())
+                    .map(|gap| WordBoundary {
+                        offset: line.offset + line.offsets[gap],
+                        confidence: self.confidence(&line, &words, gap),
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    fn line_words(&self, line: &Line) -> Vec<Word> {
+        self.segment(line)
+            .into_iter()
+            .map(|span| Word {
+                surface: line.surface(span).to_string(),
+                tags: (0..self.num_tags())
+                    .map(|lev| self.tag(line, span, lev))
+                    .collect(),
+                offset_from: line.offset + line.offsets[span.0],
+                offset_to: line.offset + line.offsets[span.1],
+            })
+            .collect()
+    }
+
+    /// Spans of the words in the line, in characters.
+    fn segment(&self, line: &Line) -> Vec<(usize, usize)> {
+        let len = line.chars.len();
+        if len == 0 {
+            return Vec::new();
+        }
+        let words = self.dictionary_words(line);
+        let mut spans = Vec::new();
+        let mut start = 0;
+        for gap in 1..len {
+            if self.confidence(line, &words, gap) > 0.0 {
+                spans.push((start, gap));
+                start = gap;
+            }
+        }
+        spans.push((start, len));
+        spans
+    }
+
+    /// The margin of the gap before the `gap`th character, positive if it is a word boundary.
+    ///
+    /// Without a word segmentation model, a line is a word.
+    fn confidence(&self, line: &Line, words: &[(usize, usize, u32)], gap: usize) -> f64 {
+        let ws = match &self.ws {
+            Some(ws) => ws,
+            None => return -1.0,
+        };
+        let features = ws_features(&self.window, &line.chars, &line.types, words, gap);
+        let margin = ws.margin(&features);
+        if ws.first_is_positive() {
+            margin
+        } else {
+            -margin
+        }
+    }
+
+    /// Every occurrence of a dictionary word in the line as `(start, end, dictionaries)`.
+    fn dictionary_words(&self, line: &Line) -> Vec<(usize, usize, u32)> {
+        let mut words = Vec::new();
+        let mut word = String::new();
+        for start in 0..line.chars.len() {
+            word.clear();
+            for (end, &c) in line
+                .chars
+                .iter()
+                .enumerate()
+                .skip(start)
+                .take(self.max_word_len)
+            {
+                word.push(c);
+                if let Some(&id) = self.word_ids.get(&word) {
+                    words.push((start, end + 1, self.dictionary[id].in_dictionaries));
+                }
+            }
+        }
+        words
+    }
+
+    /// The tag of the tag level `lev` of the word, or an empty string if it is unknown.
+    fn tag(&self, line: &Line, span: (usize, usize), lev: usize) -> String {
+        let word = line.chars[span.0..span.1].iter().collect::<String>();
+        let entry = self.word_ids.get(&word).map(|&id| &self.dictionary[id]);
+
+        let (tags, model) = match entry.and_then(|entry| Some((entry.tags.get(lev)?, entry))) {
+            Some((tags, _)) if tags.len() == 1 => return tags[0].clone(),
+            Some((tags, entry)) if !tags.is_empty() => {
+                (tags, entry.tag_models.get(lev).and_then(Option::as_ref))
+            }
+            _ => match self.tag_levels.get(lev) {
+                Some(TagLevel {
+                    model: None,
+                    language_model: Some(language_model),
+                    ..
+                }) => return self.unknown_tag(&line.chars[span.0..span.1], lev, language_model),
+                Some(level) => (&level.tags, level.model.as_ref()),
+                None => return String::new(),
+            },
+        };
+
+        let label = match model {
+            Some(model) => {
+                let dictionaries = entry.map_or(0, |entry| entry.in_dictionaries);
+                let features =
+                    tag_features(&self.window, &line.chars, &line.types, span, dictionaries);
+                model.classify(&features)
+            }
+            // Without a model, the first tag is the most frequent one.
+            None => 1,
+        };
+        // Labels are 1-based indices of the tags.
+        usize::try_from(label - 1)
+            .ok()
+            .and_then(|i| tags.get(i))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// The tag of the tag level `lev` of an unknown word, joining the tags of its subwords.
+    ///
+    /// The candidates are searched with a beam from the beginning of the word, each scored by the
+    /// probabilities of its subword tags and by `language_model`.
+    fn unknown_tag(&self, word: &[char], lev: usize, language_model: &LanguageModel) -> String {
+        // Partial tags of the first `i` characters with their log probabilities.
+        let mut partials = vec![Vec::<(String, f64)>::new(); word.len() + 1];
+        partials[0].push((String::new(), 0.0));
+        let mut subword = String::new();
+        for start in 0..word.len() {
+            let mut prefixes = std::mem::take(&mut partials[start]);
+            // Stable, so that ties keep the order in which the candidates were found.
+            prefixes.sort_by(|a, b| b.1.total_cmp(&a.1));
+            prefixes.truncate(UNK_BEAM);
+            if prefixes.is_empty() {
+                continue;
+            }
+            subword.clear();
+            for end in start + 1..=word.len().min(start + self.max_subword_len) {
+                subword.push(word[end - 1]);
+                let tags = match self.subwords.get(&subword).and_then(|tags| tags.get(lev)) {
+                    Some(tags) => tags,
+                    None => continue,
+                };
+                for (tag, prob) in tags {
+                    for (prefix, score) in &prefixes {
+                        partials[end].push((format!("{}{}", prefix, tag), score + prob.ln()));
+                    }
+                }
+            }
+        }
+
+        let mut best: Option<(String, f64)> = None;
+        for (tag, score) in partials.pop().unwrap_or_default() {
+            let score = score + language_model.score(&tag);
+            let better = match &best {
+                Some((_, best)) => score > *best,
+                None => true,
+            };
+            if better {
+                best = Some((tag, score));
+            }
+        }
+        best.map_or_else(|| String::from(DEFAULT_TAG), |(tag, _)| tag)
+    }
+}
+
+fn lines(text: &str) -> impl Iterator<Item = Line<'_>> {
+    word::lines(text).map(|(offset, line)| Line::new(line, offset))
+}
+
+impl Tokenize for Model {
+    type Error = std::convert::Infallible;
+
+    #[inline]
+    fn tokenize(&mut self, text: &str) -> Result<String, Self::Error> {
+        Ok(Model::tokenize(self, text))
+    }
+
+    #[inline]
+    fn tokenize_words(&mut self, text: &str) -> Result<Vec<Word>, Self::Error> {
+        Ok(Model::tokenize_words(self, text))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::model::{
+        Config, DictionaryWord, FeatureLookup, FileFormat, LinearModel, SubwordEntry, Weight,
+    };
+    use crate::Encoding;
+
+    /// Splits after `は`, and tags `東京` as a noun and the others by the global model.
+    fn model() -> Model {
+        Model::new(kytea_model())
+    }
+
+    fn kytea_model() -> KyteaModel {
+        let features = |features: &[(&str, Weight)]| {
+            features
+                .iter()
+                .map(|&(f, w)| (f.to_string(), vec![w]))
+                .collect()
+        };
+        KyteaModel {
+            version: String::from("0.4.7"),
+            format: FileFormat::Text,
+            encoding: Encoding::Utf8,
+            config: Config {
+                options: vec![
+                    (String::from("numtags"), String::from("1")),
+                    (String::from("charw"), String::from("1")),
+                    (String::from("charn"), String::from("1")),
+                    (String::from("typew"), String::from("1")),
+                    (String::from("typen"), String::from("1")),
+                ],
+            },
+            ws_model: Some(LinearModel {
+                labels: vec![1, -1],
+                solver: 1,
+                bias: 1.0,
+                multiplier: 0.5,
+                features: features(&[("X-1は", 4), ("D0L2", 4), ("T-1k", 1)]),
+                lookup: Some(FeatureLookup {
+                    biases: vec![-2],
+                    ..Default::default()
+                }),
+            }),
+            dictionary: vec![DictionaryWord {
+                surface: String::from("東京"),
+                tags: vec![vec![String::from("名詞")]],
+                in_dictionaries: 1,
+                tag_models: vec![None],
+            }],
+            subword_dictionary: Vec::new(),
+            tag_levels: vec![crate::model::TagLevel {
+                tags: vec![String::from("助詞"), String::from("動詞")],
+                model: Some(LinearModel {
+                    labels: vec![1, 2],
+                    solver: 1,
+                    bias: -1.0,
+                    multiplier: 1.0,
+                    features: features(&[("Sは", 2), ("S/", 2), ("TU", -1)]),
+                    lookup: None,
+                }),
+                language_model: None,
+            }],
+        }
+    }
+
+    #[test]
+    fn tokenize() {
+        let model = model();
+        assert_eq!(
+            model.tokenize("東京は/\n\n行く\n"),
+            "東京/名詞\tは/助詞\t\\//助詞\n\n行く/動詞\n"
+        );
+
+        let words = model.tokenize_words("ａ\n東京は");
+        let spans = words
+            .iter()
+            .map(|w| (w.surface.as_str(), w.offset_from, w.offset_to))
+            .collect::<Vec<_>>();
+        assert_eq!(spans, [("ａ", 0, 3), ("東京", 4, 10), ("は", 10, 13)]);
+
+        let boundaries = model.tokenize_to_boundaries("東京は");
+        assert_eq!(boundaries.len(), 2);
+        assert_eq!(boundaries[1].offset, 6);
+        assert!(boundaries[1].is_boundary());
+        assert!(!boundaries[0].is_boundary());
+    }
+    #[test]
+    fn tag_unknown_words() {
+        let mut model = kytea_model();
+        let subword = |surface: &str, tags: &[(&str, f64)]| SubwordEntry {
+            surface: surface.to_string(),
+            tags: vec![
+                Vec::new(),
+                tags.iter().map(|&(tag, p)| (tag.to_string(), p)).collect(),
+            ],
+        };
+        model.subword_dictionary = vec![
+            subword("行", &[("い", 0.6), ("ぎょう", 0.4)]),
+            subword("く", &[("く", 1.0)]),
+            subword("行く", &[("ゆく", 0.3)]),
+        ];
+        let probs = [("い", -1.0), ("く", -1.0), ("ゆ", -5.0), ("\0", -0.1)];
+        model.tag_levels.push(crate::model::TagLevel {
+            tags: Vec::new(),
+            model: None,
+            language_model: Some(crate::model::LanguageModel {
+                n: 1,
+                vocabulary_size: 10,
+                probs: probs.iter().map(|&(c, p)| (c.to_string(), p)).collect(),
+                fallbacks: Vec::new(),
+            }),
+        });
+        let model = Model::new(model);
+
+        let words = model.tokenize_words("行く\nａ");
+        let tags = words
+            .iter()
+            .map(|w| (w.surface.as_str(), w.tags[1].as_str()))
+            .collect::<Vec<_>>();
+        // `いく` is more probable than `ゆく` by the language model, and `ぎょうく` has
+        // characters unknown to it; `ａ` has no subword.
+        assert_eq!(tags, [("行く", "いく"), ("ａ", DEFAULT_TAG)]);
+        assert_eq!(words[0].tags[0], "動詞");
+    }
+}
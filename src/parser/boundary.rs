@@ -0,0 +1,140 @@
+use crate::{ESCAPE, HAS_BOUND, NO_BOUND, SKIP_BOUND, TAG_DELIM, UNK_BOUND};
+
+/// Word boundary of a gap between two characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Boundary {
+    /// `|`: there is a word boundary.
+    Bound,
+    /// `-`: there is no word boundary.
+    NoBound,
+    /// ` ` or `?`: KyTea is not confident enough of the gap.
+    Unknown,
+}
+
+impl Boundary {
+    fn from_mark(c: u8) -> Option<Self> {
+        match c {
+            HAS_BOUND => Some(Self::Bound),
+            NO_BOUND => Some(Self::NoBound),
+            UNK_BOUND | SKIP_BOUND => Some(Self::Unknown),
+            _ => None,
+        }
+    }
+}
+
+/// Iterator over the gaps of a line of KyTea's partial annotation output (`-out part`).
+///
+/// With `-confidence`, KyTea writes `|` or `-` only on the gaps whose margin exceeds the
+/// threshold, and leaves the others blank:
+/// `コ-ー-パ-ス/名詞|で/助詞|あ る`.
+///
+/// Each item is the offset (byte index) of the gap in the unannotated text and its boundary.
+/// Tags are skipped.
+#[derive(Debug, Clone)]
+pub struct Boundaries<'a> {
+    inner: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Boundaries<'a> {
+    #[inline]
+    pub fn from_line(line: &'a str) -> Self {
+        Self {
+            inner: line.as_bytes(),
+            offset: 0,
+        }
+    }
+
+    fn skip_char(&mut self) {
+        let mut len = 1;
+        while len < self.inner.len() && (self.inner[len] & 0xc0) == 0x80 {
+            len += 1;
+        }
+        self.inner = &self.inner[len..];
+        self.offset += len;
+    }
+
+    fn skip_tags(&mut self) {
+        let mut prev_char = 0u8;
+        for (i, &c) in self.inner.iter().enumerate() {
+            if c == HAS_BOUND && prev_char != ESCAPE {
+                self.inner = &self.inner[i..];
+                return;
+            }
+
+            prev_char = if c == ESCAPE && prev_char == ESCAPE {
+                0
+            } else {
+                c
+            };
+        }
+        self.inner = &[];
+    }
+}
+
+impl Iterator for Boundaries<'_> {
+    type Item = (usize, Boundary);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.inner.is_empty() {
+            return None;
+        }
+
+        if self.inner[0] == ESCAPE && self.inner.len() > 1 {
+            self.inner = &self.inner[1..];
+        }
+        self.skip_char();
+
+        let &c = self.inner.first()?;
+        if c == TAG_DELIM {
+            self.skip_tags();
+            if self.inner.is_empty() {
+                return None;
+            }
+            self.inner = &self.inner[1..];
+            return Some((self.offset, Boundary::Bound));
+        }
+
+        if let Some(boundary) = Boundary::from_mark(c) {
+            self.inner = &self.inner[1..];
+            Some((self.offset, boundary))
+        } else {
+            Some((self.offset, Boundary::Unknown))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn boundaries() {
+        let mut it = Boundaries::from_line("");
+        assert_eq!(it.next(), None);
+
+        let mut it = Boundaries::from_line("a");
+        assert_eq!(it.next(), None);
+
+        let mut it = Boundaries::from_line("a|b-c d?e");
+        assert_eq!(it.next(), Some((1, Boundary::Bound)));
+        assert_eq!(it.next(), Some((2, Boundary::NoBound)));
+        assert_eq!(it.next(), Some((3, Boundary::Unknown)));
+        assert_eq!(it.next(), Some((4, Boundary::Unknown)));
+        assert_eq!(it.next(), None);
+
+        let mut it = Boundaries::from_line("コ-ー-パ-ス/名詞/こーぱす|で/助詞|あ る");
+        assert_eq!(it.next(), Some((3, Boundary::NoBound)));
+        assert_eq!(it.next(), Some((6, Boundary::NoBound)));
+        assert_eq!(it.next(), Some((9, Boundary::NoBound)));
+        assert_eq!(it.next(), Some((12, Boundary::Bound)));
+        assert_eq!(it.next(), Some((15, Boundary::Bound)));
+        assert_eq!(it.next(), Some((18, Boundary::Unknown)));
+        assert_eq!(it.next(), None);
+
+        let mut it = Boundaries::from_line("\\|-a/\\|/b|c/d");
+        assert_eq!(it.next(), Some((1, Boundary::NoBound)));
+        assert_eq!(it.next(), Some((2, Boundary::Bound)));
+        assert_eq!(it.next(), None);
+    }
+}
@@ -0,0 +1,91 @@
+use crate::parser::tag::TagIterator;
+use crate::parser::Tags;
+use crate::ELEM_DELIM;
+
+/// Ranked tag candidates of a tag level, as written by KyTea's confidence output (`-out conf`).
+///
+/// A tag level looks like `tag1&score1&tag2&score2`, the best candidate first.
+/// Each tag is parsed as `T`, so `Candidates<PoS>` yields the PoS candidates.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Candidates<T>(pub Vec<(T, f64)>);
+
+impl<T> Candidates<T> {
+    #[inline]
+    pub fn best(&self) -> Option<&T> {
+        self.0.first().map(|(tag, _)| tag)
+    }
+
+    #[inline]
+    pub fn best_score(&self) -> Option<f64> {
+        self.0.first().map(|&(_, score)| score)
+    }
+
+    #[inline]
+    pub fn iter(&self) -> std::slice::Iter<'_, (T, f64)> {
+        self.0.iter()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl<'a, T: Tags<'a>> Tags<'a> for Candidates<T> {
+    fn from_tags<I: Iterator<Item = &'a str>>(tags: &mut I) -> Self {
+        let mut candidates = Vec::new();
+        if let Some(tag) = tags.next() {
+            let mut elems = TagIterator::with_delim(tag, ELEM_DELIM);
+            while let Some(tag) = elems.next() {
+                let score = elems.next().and_then(|s| s.parse().ok()).unwrap_or(0.0);
+                candidates.push((T::from_tags(&mut std::iter::once(tag)), score));
+            }
+        }
+        Self(candidates)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{PoS, Surface, WordIterator};
+
+    #[test]
+    fn candidates_from_tags() {
+        let mut tags = TagIterator::from("a/名詞&0.9&動詞&0.1/あ&1");
+        let tags = <(Surface, Candidates<PoS>, Candidates<&str>)>::from_tags(&mut tags);
+        assert_eq!(tags.0, Surface("a"));
+        assert_eq!(tags.1, Candidates(vec![(PoS::名詞, 0.9), (PoS::動詞, 0.1)]));
+        assert_eq!(tags.2, Candidates(vec![("あ", 1.0)]));
+        assert_eq!(tags.1.best(), Some(&PoS::名詞));
+        assert_eq!(tags.1.best_score(), Some(0.9));
+
+        let mut tags = TagIterator::from("a//");
+        let tags = <(Surface, Candidates<PoS>)>::from_tags(&mut tags);
+        assert!(tags.1.is_empty());
+        assert_eq!(tags.1.best(), None);
+
+        let mut tags = TagIterator::from("a/\\&&0.5&b&x");
+        let tags = <(Surface, Candidates<&str>)>::from_tags(&mut tags);
+        assert_eq!(tags.1, Candidates(vec![("\\&", 0.5), ("b", 0.0)]));
+    }
+
+    #[test]
+    fn candidates_word_iterator() {
+        let words = "吾輩/代名詞&0.8&名詞&0.2\tは/助詞&1.0\n";
+        let mut it = WordIterator::<(Surface, Candidates<PoS>)>::from_lines(words);
+        let (surface, candidates) = it.next().unwrap();
+        assert_eq!(surface, Surface("吾輩"));
+        assert_eq!(candidates.len(), 2);
+        assert_eq!(candidates.best(), Some(&PoS::代名詞));
+        let (surface, candidates) = it.next().unwrap();
+        assert_eq!(surface, Surface("は"));
+        assert_eq!(candidates.iter().next(), Some(&(PoS::助詞, 1.0)));
+        assert!(it.next().is_none());
+    }
+}
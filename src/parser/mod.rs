@@ -1,8 +1,14 @@
+mod boundary;
+pub use boundary::{Boundaries, Boundary};
+
+mod candidates;
+pub use candidates::Candidates;
+
 mod surface;
 pub use surface::Surface;
 
 mod tag;
-use tag::TagIterator;
+pub(crate) use tag::TagIterator;
 pub use tag::{DefaultTags, Tags};
 
 mod words;
@@ -38,11 +38,7 @@ impl_tags! { T1, T2, T3, T4, T5, T6, T7, T8 }
 
 impl<'a> Tags<'a> for &'a str {
     fn from_tags<I: Iterator<Item = &'a str>>(tags: &mut I) -> Self {
-        if let Some(tag) = tags.next() {
-            tag
-        } else {
-            ""
-        }
+        tags.next().unwrap_or_default()
     }
 }
 
@@ -59,6 +55,7 @@ pub type DefaultTags<'a> = (Surface<'a>, PoS, &'a str);
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub(crate) struct TagIterator<'a> {
     inner: &'a str,
+    delim: u8,
 }
 
 impl<'a> Iterator for TagIterator<'a> {
@@ -85,13 +82,19 @@ impl<'a> Iterator for TagIterator<'a> {
 impl<'a> TagIterator<'a> {
     #[inline]
     pub(crate) fn from(inner: &'a str) -> Self {
-        Self { inner }
+        Self::with_delim(inner, TAG_DELIM)
+    }
+
+    /// Splits `inner` by unescaped `delim`'s instead of [`TAG_DELIM`].
+    #[inline]
+    pub(crate) fn with_delim(inner: &'a str, delim: u8) -> Self {
+        Self { inner, delim }
     }
 
     fn find_next_slash(self) -> usize {
         let mut prev_char = 0u8;
         for (i, &c) in self.inner.as_bytes().iter().enumerate() {
-            if c == TAG_DELIM && prev_char != ESCAPE {
+            if c == self.delim && prev_char != ESCAPE {
                 return i;
             }
 
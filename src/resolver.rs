@@ -0,0 +1,164 @@
+//! Lookup of the KyTea model file.
+
+use std::ffi::OsString;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// File name of the model under a data directory, as installed by KyTea.
+const MODEL_FILE: &str = "kytea/model.bin";
+
+/// Prefix of KyTea found by `build.rs`, if any.
+const BUILD_PREFIX: Option<&str> = option_env!("KYTEA_PREFIX");
+
+/// The default prefix of KyTea's `./configure`.
+const DEFAULT_PREFIX: &str = "/usr/local";
+
+/// Finds a KyTea model file, searching in order:
+///
+/// 1. `$KYTEA_MODEL`;
+/// 2. the paths given by [`ModelResolver::path()`], in the order given;
+/// 3. `kytea/model.bin` in `$XDG_DATA_HOME` (`~/.local/share`) and `$XDG_DATA_DIRS`
+///    (`/usr/local/share:/usr/share`);
+/// 4. `share/kytea/model.bin` in the prefix KyTea was installed to.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ModelResolver {
+    paths: Vec<PathBuf>,
+}
+
+impl ModelResolver {
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.paths.push(path.into());
+        self
+    }
+
+    pub fn paths(mut self, paths: impl IntoIterator<Item = impl Into<PathBuf>>) -> Self {
+        self.paths.extend(paths.into_iter().map(Into::into));
+        self
+    }
+
+    /// Every location searched, in order, whether or not it exists.
+    pub fn candidates(&self) -> Vec<PathBuf> {
+        self.candidates_with(|var| std::env::var_os(var))
+    }
+
+    /// Returns the first candidate which is a file.
+    #[inline]
+    pub fn resolve(&self) -> Result<PathBuf, ModelNotFound> {
+        first_file(self.candidates())
+    }
+
+    fn candidates_with(&self, var: impl Fn(&str) -> Option<OsString>) -> Vec<PathBuf> {
+        let var = |name| var(name).filter(|value| !value.is_empty());
+        let mut candidates = Vec::new();
+
+        candidates.extend(var("KYTEA_MODEL").map(PathBuf::from));
+        candidates.extend(self.paths.iter().cloned());
+
+        let data_home = var("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| var("HOME").map(|home| Path::new(&home).join(".local/share")));
+        let data_dirs =
+            var("XDG_DATA_DIRS").unwrap_or_else(|| "/usr/local/share:/usr/share".into());
+        let data_dirs = data_home
+            .into_iter()
+            .chain(std::env::split_paths(&data_dirs));
+        candidates.extend(data_dirs.map(|dir| dir.join(MODEL_FILE)));
+
+        let prefixes = BUILD_PREFIX.into_iter().chain(Some(DEFAULT_PREFIX));
+        candidates.extend(prefixes.map(|prefix| Path::new(prefix).join("share").join(MODEL_FILE)));
+
+        let mut seen = std::collections::HashSet::new();
+        candidates.retain(|path| seen.insert(path.clone()));
+        candidates
+    }
+}
+
+fn first_file(tried: Vec<PathBuf>) -> Result<PathBuf, ModelNotFound> {
+    match tried.iter().find(|path| path.is_file()) {
+        Some(path) => Ok(path.clone()),
+        None => Err(ModelNotFound { tried }),
+    }
+}
+
+/// Searches the default locations of [`ModelResolver`].
+#[inline]
+pub fn find_model() -> Result<PathBuf, ModelNotFound> {
+    ModelResolver::new().resolve()
+}
+
+/// No model file was found in any location searched by [`ModelResolver`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelNotFound {
+    pub tried: Vec<PathBuf>,
+}
+
+impl fmt::Display for ModelNotFound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("no KyTea model found; tried ")?;
+        for (i, path) in self.tried.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}", path.display())?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ModelNotFound {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn candidates_order() {
+        let resolver = ModelResolver::new()
+            .path("a.bin")
+            .path("/usr/share/kytea/model.bin");
+        let candidates = resolver.candidates_with(|var| match var {
+            "KYTEA_MODEL" => Some("env.bin".into()),
+            "HOME" => Some("/home/u".into()),
+            "XDG_DATA_DIRS" => Some("/x:/usr/share".into()),
+            _ => None,
+        });
+        let expected = [
+            "env.bin",
+            "a.bin",
+            "/usr/share/kytea/model.bin",
+            "/home/u/.local/share/kytea/model.bin",
+            "/x/kytea/model.bin",
+        ]
+        .iter()
+        .map(PathBuf::from)
+        .collect::<Vec<_>>();
+        // `/usr/share` of `XDG_DATA_DIRS` is a duplicate.
+        assert_eq!(candidates[..expected.len()], expected);
+        assert_eq!(
+            candidates.last().unwrap(),
+            Path::new("/usr/local/share/kytea/model.bin")
+        );
+    }
+
+    #[test]
+    fn resolve_first_file() {
+        let dir = std::env::temp_dir().join(format!("kytea-resolver-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let model = dir.join("model.bin");
+        std::fs::write(&model, b"").unwrap();
+
+        let missing = dir.join("missing.bin");
+        let resolved = first_file(vec![missing.clone(), dir.clone(), model.clone()]);
+        let err = first_file(vec![missing.clone(), dir.clone()]).unwrap_err();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(resolved.unwrap(), model);
+        assert_eq!(err.tried, [missing, dir]);
+        assert!(err.to_string().contains("missing.bin"));
+    }
+}
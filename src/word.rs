@@ -0,0 +1,254 @@
+//! Words analyzed by KyTea, shared by the backends.
+
+use crate::Tags;
+#[cfg(any(feature = "cmd", feature = "native", feature = "mock"))]
+use crate::{ESCAPE, TAG_DELIM, WORD_DELIM};
+
+#[cfg(feature = "json")]
+use serde::{Deserialize, Serialize};
+
+/// A word analyzed by KyTea, read directly from the `KyteaSentence` without going through the
+/// text output.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct Word {
+    /// Surface form of the word, unescaped.
+    pub surface: String,
+    /// The best tag of each tag level; an empty string if the level is not tagged.
+    pub tags: Vec<String>,
+    /// Offset (byte index) of the first byte of the word in the input.
+    pub offset_from: usize,
+    /// Offset (byte index) of the last byte of the word in the input + 1.
+    pub offset_to: usize,
+}
+
+impl Word {
+    /// Parses the surface and the tags the same way as [`WordIterator`](crate::WordIterator) does.
+    pub fn to_tags<'a, T: Tags<'a>>(&'a self) -> T {
+        let mut tags =
+            std::iter::once(self.surface.as_str()).chain(self.tags.iter().map(String::as_str));
+        T::from_tags(&mut tags)
+    }
+}
+
+/// Words of a document tokenized by [`Model::tokenize_batch()`](crate::ffi::Model::tokenize_batch).
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct Sentence {
+    pub words: Vec<Word>,
+}
+
+/// A word analyzed by KyTea with the ranked tag candidates of each tag level.
+///
+/// The number of candidates is bounded by [`ModelConfig::tag_max()`](crate::ffi::ModelConfig::tag_max).
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct WordCandidates {
+    /// Surface form of the word, unescaped.
+    pub surface: String,
+    /// `(tag, score)` pairs of each tag level, the best first; empty if the level is not tagged.
+    pub tags: Vec<Vec<(String, f64)>>,
+    /// Offset (byte index) of the first byte of the word in the input.
+    pub offset_from: usize,
+    /// Offset (byte index) of the last byte of the word in the input + 1.
+    pub offset_to: usize,
+}
+
+impl WordCandidates {
+    /// Returns the best candidate of the tag level `lev`.
+    pub fn best(&self, lev: usize) -> Option<(&str, f64)> {
+        self.tags
+            .get(lev)
+            .and_then(|tags| tags.first())
+            .map(|(tag, score)| (tag.as_str(), *score))
+    }
+}
+
+impl From<WordCandidates> for Word {
+    fn from(word: WordCandidates) -> Self {
+        Self {
+            tags: word
+                .tags
+                .into_iter()
+                .map(|tags| {
+                    tags.into_iter()
+                        .next()
+                        .map(|(tag, _)| tag)
+                        .unwrap_or_default()
+                })
+                .collect(),
+            surface: word.surface,
+            offset_from: word.offset_from,
+            offset_to: word.offset_to,
+        }
+    }
+}
+
+/// Confidence of a gap between two characters.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct WordBoundary {
+    /// Offset (byte index) of the gap in the input, i.e. the first byte of the next character.
+    pub offset: usize,
+    /// Margin of the word segmentation classifier.
+    pub confidence: f64,
+}
+
+impl WordBoundary {
+    /// Whether KyTea put a word boundary on the gap.
+    #[inline]
+    pub fn is_boundary(self) -> bool {
+        self.confidence > 0.0
+    }
+}
+
+/// Tokenization common to the backends, so that code using a model can be tested with
+/// [`mock::Model`](crate::mock::Model) instead.
+pub trait Tokenize {
+    type Error;
+
+    /// Tokenizes `text` and returns the output in the format read by
+    /// [`WordIterator`](crate::WordIterator), one line per line of `text`.
+    fn tokenize(&mut self, text: &str) -> Result<String, Self::Error>;
+
+    /// Tokenizes `text` into words; offsets are byte indices in `text`.
+    fn tokenize_words(&mut self, text: &str) -> Result<Vec<Word>, Self::Error>;
+}
+
+/// Lines of `text` with their offsets, as KyTea reads them: a trailing newline does not start
+/// an empty line.
+#[cfg(any(feature = "cmd", feature = "native", feature = "mock"))]
+pub(crate) fn lines(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    let mut offset = 0;
+    text.split_terminator('\n').map(move |line| {
+        let start = offset;
+        offset += line.len() + 1;
+        (start, line)
+    })
+}
+
+/// Writes the words of a line and a newline as KyTea does, escaping the delimiters read by
+/// [`WordIterator`](crate::WordIterator); tags stop at the first empty one.
+#[cfg(any(feature = "native", feature = "mock"))]
+pub(crate) fn write_words(output: &mut String, words: &[Word]) {
+    let write_escaped = |output: &mut String, s: &str| {
+        for c in s.chars() {
+            if [WORD_DELIM, TAG_DELIM, ESCAPE]
+                .iter()
+                .any(|&b| c == char::from(b))
+            {
+                output.push(char::from(ESCAPE));
+            }
+            output.push(c);
+        }
+    };
+    for (i, word) in words.iter().enumerate() {
+        if i > 0 {
+            output.push(char::from(WORD_DELIM));
+        }
+        write_escaped(output, &word.surface);
+        for tag in word.tags.iter().take_while(|tag| !tag.is_empty()) {
+            output.push(char::from(TAG_DELIM));
+            write_escaped(output, tag);
+        }
+    }
+    output.push('\n');
+}
+
+/// Reads the words of a line written as [`write_words()`] does, locating them in `line`, the
+/// input at `offset` in the whole text.
+///
+/// A word whose surface is not found in `line` is given an empty span where the search stopped.
+#[cfg(feature = "cmd")]
+pub(crate) fn read_words(output: &str, line: &str, offset: usize) -> Vec<Word> {
+    let mut words = Vec::new();
+    let mut pos = 0;
+    for word in split_escaped(output, WORD_DELIM) {
+        if word.is_empty() {
+            continue;
+        }
+        let mut elems = split_escaped(word, TAG_DELIM).into_iter().map(unescape);
+        let surface = elems.next().unwrap_or_default();
+        let (from, to) = match line[pos..].find(&surface) {
+            Some(i) => (pos + i, pos + i + surface.len()),
+            None => (pos, pos),
+        };
+        pos = to;
+        words.push(Word {
+            surface,
+            tags: elems.collect(),
+            offset_from: offset + from,
+            offset_to: offset + to,
+        });
+    }
+    words
+}
+
+/// Splits `s` at each `delim` not escaped by [`ESCAPE`].
+#[cfg(feature = "cmd")]
+fn split_escaped(s: &str, delim: u8) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut escaped = false;
+    for (i, b) in s.bytes().enumerate() {
+        if escaped {
+            escaped = false;
+        } else if b == ESCAPE {
+            escaped = true;
+        } else if b == delim {
+            parts.push(&s[start..i]);
+            start = i + 1;
+        }
+    }
+    parts.push(&s[start..]);
+    parts
+}
+
+#[cfg(feature = "cmd")]
+fn unescape(s: &str) -> String {
+    let mut unescaped = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == char::from(ESCAPE) {
+            unescaped.extend(chars.next());
+        } else {
+            unescaped.push(c);
+        }
+    }
+    unescaped
+}
+
+#[cfg(all(test, feature = "cmd"))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn read_escaped_words() {
+        let line = "x a/b\\c\tで";
+        let output = "x/記号\t /補助記号\ta\\/b\\\\c/名詞/えーびーしー\t\\\t/空白\tで/助詞";
+        let words = read_words(output, line, 4)
+            .into_iter()
+            .map(|w| (w.surface, w.tags, w.offset_from, w.offset_to))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            words,
+            [
+                (String::from("x"), vec![String::from("記号")], 4, 5),
+                (String::from(" "), vec![String::from("補助記号")], 5, 6),
+                (
+                    String::from("a/b\\c"),
+                    vec![String::from("名詞"), String::from("えーびーしー")],
+                    6,
+                    11
+                ),
+                (String::from("\t"), vec![String::from("空白")], 11, 12),
+                (String::from("で"), vec![String::from("助詞")], 12, 15),
+            ]
+        );
+
+        // A surface not in the line does not move the search.
+        let words = read_words("y/記号\tx/記号", "x", 0);
+        assert_eq!((words[0].offset_from, words[0].offset_to), (0, 0));
+        assert_eq!((words[1].offset_from, words[1].offset_to), (0, 1));
+    }
+}
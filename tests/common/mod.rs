@@ -0,0 +1,67 @@
+//! Helpers shared by the tests against libkytea.
+
+#![allow(dead_code)]
+
+use std::path::PathBuf;
+
+use kytea_tokenizer::ffi::{AnnotatedSentence, ModelFormat, Trainer, Word};
+
+/// A directory removed on drop.
+pub struct TempDir(pub PathBuf);
+
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("kytea-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn word(surface: &str, pos: &str, pron: &str) -> Word {
+    Word {
+        surface: surface.to_string(),
+        tags: vec![pos.to_string(), pron.to_string()],
+        ..Default::default()
+    }
+}
+
+/// The sentences the models are trained on, tagged with the PoS and the pronunciation.
+pub fn sentences() -> Vec<Vec<Word>> {
+    vec![
+        vec![
+            word("東京", "名詞", "とうきょう"),
+            word("に", "助詞", "に"),
+            word("行く", "動詞", "いく"),
+        ],
+        vec![
+            word("京都", "名詞", "きょうと"),
+            word("に", "助詞", "に"),
+            word("住む", "動詞", "すむ"),
+        ],
+        vec![
+            word("大阪", "名詞", "おおさか"),
+            word("へ", "助詞", "へ"),
+            word("行く", "動詞", "いく"),
+        ],
+    ]
+}
+
+/// Trains a model in `dir` by libkytea, as `train-kytea` does.
+pub fn train(dir: &TempDir, format: ModelFormat) -> PathBuf {
+    let model = dir.0.join(match format {
+        ModelFormat::Binary => "model.bin",
+        ModelFormat::Text => "model.txt",
+    });
+    Trainer::new()
+        .sentences(sentences().into_iter().map(AnnotatedSentence::Full))
+        .model_format(format)
+        .train(&model)
+        .unwrap();
+    model
+}
@@ -0,0 +1,232 @@
+//! Tests of the `ffi` backend against libkytea, on a model trained from a few sentences.
+
+#![cfg(feature = "ffi")]
+
+mod common;
+use common::TempDir;
+
+use kytea_tokenizer::ffi::{AnnotatedSentence, Trainer};
+
+use std::path::PathBuf;
+
+use kytea_tokenizer::dictionary::{
+    write_dictionary, write_dictionary_with_encoding, DictionaryEntry,
+};
+use kytea_tokenizer::ffi::{
+    Istream, Model, ModelConfig, ModelFormat, ModelPool, Ostream, OutputFormat,
+};
+use kytea_tokenizer::{Boundary, Encoding};
+
+fn train(dir: &TempDir) -> PathBuf {
+    common::train(dir, ModelFormat::Binary)
+}
+
+#[test]
+fn user_dictionary_is_loaded() {
+    let dir = TempDir::new("user-dictionary");
+    let model = train(&dir);
+    let dictionary = dir.0.join("dictionary.txt");
+    let entries = [
+        DictionaryEntry::new("名古屋", ["名詞"]),
+        DictionaryEntry::new("札幌", ["名詞"]),
+    ];
+    write_dictionary(&dictionary, &entries).unwrap();
+
+    let without = Model::try_new(&model).unwrap();
+    let with = Model::with_config(&model, &ModelConfig::new().dictionary(&dictionary)).unwrap();
+    assert_eq!(
+        with.dictionary_size(),
+        without.dictionary_size() + entries.len()
+    );
+}
+
+#[test]
+fn missing_user_dictionary() {
+    let dir = TempDir::new("missing-dictionary");
+    let model = train(&dir);
+    let config = ModelConfig::new().dictionary(dir.0.join("missing.txt"));
+    assert!(Model::with_config(&model, &config).is_err());
+}
+
+#[test]
+fn tokenize_unterminated_line() {
+    let dir = TempDir::new("unterminated");
+    let mut model = Model::try_new(train(&dir)).unwrap();
+    let terminated = model.tokenize("東京に行く\n").unwrap();
+    assert_eq!(model.tokenize("東京に行く").unwrap(), terminated);
+
+    let input = Istream::from_text("東京に行く\n");
+    let mut output = Ostream::new();
+    model.tokenize_to_str(&input, &mut output).unwrap();
+    assert_eq!(output.into_string().unwrap(), terminated);
+
+    let words = model.tokenize_words("京都に住む").unwrap();
+    assert_eq!(words.last().unwrap().offset_to, "京都に住む".len());
+}
+
+#[test]
+fn tokenize_reader_streams_lines() {
+    let dir = TempDir::new("reader");
+    let mut model = Model::try_new(train(&dir)).unwrap();
+    let text = "東京に行く\n\n京都に住む\n大阪へ行く\n";
+
+    let mut output = Vec::new();
+    model.tokenize_reader(text.as_bytes(), &mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert_eq!(output, model.tokenize(text).unwrap());
+    assert_eq!(output.lines().count(), text.lines().count());
+
+    let mut output = Vec::new();
+    model
+        .tokenize_reader_with_format(text.as_bytes(), &mut output, OutputFormat::Tok)
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        model.tokenize_with_format(text, OutputFormat::Tok).unwrap()
+    );
+}
+
+#[test]
+fn pool_tokenizes_text() {
+    let dir = TempDir::new("pool");
+    let path = train(&dir);
+    let pool = ModelPool::new(&path, 2).unwrap();
+    let mut model = Model::try_new(&path).unwrap();
+
+    let texts = ["東京に行く", "京都に住む\n大阪へ行く\n"];
+    std::thread::scope(|scope| {
+        for text in texts {
+            let pool = &pool;
+            scope.spawn(move || pool.tokenize_words(text).unwrap());
+        }
+    });
+    for text in texts {
+        assert_eq!(pool.tokenize(text).unwrap(), model.tokenize(text).unwrap());
+        assert_eq!(
+            pool.tokenize_words(text).unwrap(),
+            model.tokenize_words(text).unwrap()
+        );
+    }
+    assert_eq!(pool.available(), pool.size());
+}
+
+#[test]
+fn euc_jp_model() {
+    let dir = TempDir::new("euc-jp");
+    let model = dir.0.join("model.bin");
+    Trainer::new()
+        .sentences(common::sentences().into_iter().map(AnnotatedSentence::Full))
+        .encoding(Encoding::EucJp)
+        .train(&model)
+        .unwrap();
+    assert_eq!(Encoding::of_model(&model).unwrap(), Encoding::EucJp);
+
+    let dictionary = dir.0.join("dictionary.txt");
+    let entries = [DictionaryEntry::new("札幌", ["名詞"])];
+    write_dictionary_with_encoding(&dictionary, &entries, Encoding::EucJp).unwrap();
+    let config = ModelConfig::new().dictionary(&dictionary).unk_tag("未知");
+    let mut model = Model::with_config(&model, &config).unwrap();
+
+    let words = model.tokenize_words("札幌に行く").unwrap();
+    let words = words
+        .iter()
+        .map(|w| (w.surface.as_str(), w.tags[0].as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(words, [("札幌", "名詞"), ("に", "助詞"), ("行く", "動詞")]);
+    assert!(model.tokenize("ロンドンに行く").unwrap().contains("未知"));
+}
+
+#[test]
+fn constraints_override_the_model() {
+    let dir = TempDir::new("constrained");
+    let mut model = Model::try_new(train(&dir)).unwrap();
+    let surfaces = |words: Vec<kytea_tokenizer::Word>| {
+        words.into_iter().map(|w| w.surface).collect::<Vec<_>>()
+    };
+
+    let text = "東京に行く";
+    assert_eq!(
+        surfaces(model.tokenize_constrained(text, &[]).unwrap()),
+        ["東京", "に", "行く"]
+    );
+
+    // A split inside a word of the dictionary and a join across a boundary the model predicts.
+    let inside = "東".len();
+    let across = "東京に".len();
+    let words = model
+        .tokenize_constrained(
+            text,
+            &[(inside, Boundary::Bound), (across, Boundary::NoBound)],
+        )
+        .unwrap();
+    assert_eq!(surfaces(words), ["東", "京", "に行く"]);
+
+    // An unknown constraint is left to the model.
+    let words = model
+        .tokenize_constrained(text, &[(across, Boundary::Unknown)])
+        .unwrap();
+    assert_eq!(surfaces(words), ["東京", "に", "行く"]);
+}
+
+#[test]
+fn tokenize_batch_keeps_documents_apart() {
+    let dir = TempDir::new("batch");
+    let mut model = Model::try_new(train(&dir)).unwrap();
+
+    let empty: [&str; 0] = [];
+    assert!(model.tokenize_batch(&empty).unwrap().is_empty());
+
+    let docs = [
+        "東京に行く",
+        "",
+        "京都に住む\n大阪へ行く",
+        "\n",
+        "大阪へ行く\n",
+    ];
+    let sentences = model.tokenize_batch(&docs).unwrap();
+    assert_eq!(sentences.len(), docs.len());
+    for (doc, sentence) in docs.iter().zip(&sentences) {
+        for word in &sentence.words {
+            assert_eq!(&doc[word.offset_from..word.offset_to], word.surface);
+        }
+        let surfaces = sentence
+            .words
+            .iter()
+            .map(|word| word.surface.as_str())
+            .collect::<String>();
+        assert_eq!(surfaces, doc.replace('\n', ""));
+    }
+    assert!(sentences[1].words.is_empty());
+    assert!(sentences[3].words.is_empty());
+    assert_eq!(sentences[2].words.len(), 6);
+    assert_eq!(sentences[2].words[3].surface, "大阪");
+    assert_eq!(sentences[2].words[3].offset_from, "京都に住む\n".len());
+    assert_eq!(sentences[4].words.len(), 3);
+}
+
+#[cfg(feature = "model")]
+#[test]
+fn tags_are_dictionary_tags() {
+    use kytea_tokenizer::model::KyteaModel;
+
+    let dir = TempDir::new("tags");
+    let path = train(&dir);
+    let model = Model::try_new(&path).unwrap();
+    let file = KyteaModel::open(&path).unwrap();
+
+    let mut dictionary = file
+        .dictionary
+        .iter()
+        .filter_map(|word| word.tags.first())
+        .flatten()
+        .map(String::as_str)
+        .collect::<Vec<_>>();
+    dictionary.sort_unstable();
+    dictionary.dedup();
+    assert_eq!(model.tags(0), dictionary);
+    assert_eq!(model.tags(0), ["助詞", "動詞", "名詞"]);
+
+    // The global tags are only listed by the model file.
+    let all = file.tags(0);
+    assert!(model.tags(0).iter().all(|tag| all.contains(&tag.as_str())));
+}
@@ -0,0 +1,7 @@
+#!/bin/sh
+# Trains the models read by `tests/model.rs` from `corpus.txt`, with `train-kytea` of KyTea
+# 0.4.7 in `PATH`, in both formats.
+set -e
+cd "$(dirname "$0")"
+train-kytea -full corpus.txt -model model.bin
+train-kytea -full corpus.txt -model model.txt -modtext
@@ -0,0 +1,112 @@
+//! Reads model files written by KyTea, in both formats: the fixtures trained by `train-kytea`
+//! and, with `ffi`, models trained by libkytea and checked against what it reports of them.
+
+#![cfg(feature = "model")]
+
+#[cfg(feature = "ffi")]
+mod common;
+
+use std::path::Path;
+
+use kytea_tokenizer::model::{FileFormat, KyteaModel};
+use kytea_tokenizer::Encoding;
+
+/// Checks what both formats of a model trained on the sentences of `tests/fixtures/corpus.txt`
+/// have in common.
+fn check_formats(binary: &KyteaModel, text: &KyteaModel) {
+    assert_eq!(binary.format, FileFormat::Binary);
+    assert_eq!(text.format, FileFormat::Text);
+
+    for model in [binary, text] {
+        let ws = model.ws_model.as_ref().unwrap();
+        assert!(ws.multiplier > 0.0);
+        // Trained weights are moved into the lookup by KyTea.
+        let lookup = ws.lookup.as_ref().unwrap();
+        assert!(lookup
+            .char_ngrams
+            .iter()
+            .any(|(ngram, weights)| ngram == "に" && weights.iter().any(|&w| w != 0)));
+    }
+
+    // The text format prints the multipliers with less precision.
+    let same_multiplier = |binary: f64, text: f64| (binary - text).abs() <= binary.abs() * 1e-4;
+    let ws = (binary.ws_model.as_ref(), text.ws_model.as_ref());
+    assert!(same_multiplier(
+        ws.0.unwrap().multiplier,
+        ws.1.unwrap().multiplier
+    ));
+    assert_eq!(
+        ws.0.unwrap().lookup,
+        ws.1.unwrap().lookup,
+        "the formats disagree on the weights"
+    );
+    assert_eq!(binary.config, text.config);
+    assert_eq!(binary.dictionary.len(), text.dictionary.len());
+    for (b, t) in binary.dictionary.iter().zip(&text.dictionary) {
+        assert_eq!((&b.surface, &b.tags), (&t.surface, &t.tags));
+    }
+}
+
+#[test]
+#[ignore = "needs the models written by tests/fixtures/generate.sh"]
+fn read_fixtures() {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let binary = KyteaModel::open(fixtures.join("model.bin")).unwrap();
+    let text = KyteaModel::open(fixtures.join("model.txt")).unwrap();
+    check_formats(&binary, &text);
+
+    for model in [&binary, &text] {
+        assert_eq!(model.version, "0.4.7");
+        assert_eq!(model.encoding, Encoding::Utf8);
+        assert_eq!(model.num_tags(), 2);
+
+        let mut words = model
+            .dictionary
+            .iter()
+            .map(|word| word.surface.as_str())
+            .collect::<Vec<_>>();
+        words.sort_unstable();
+        assert_eq!(words, ["に", "へ", "京都", "住む", "大阪", "東京", "行く"]);
+        let tokyo = model
+            .dictionary
+            .iter()
+            .find(|word| word.surface == "東京")
+            .unwrap();
+        assert_eq!(tokyo.tags, [["名詞"], ["とうきょう"]]);
+        for tag in ["助詞", "動詞", "名詞"] {
+            assert!(model.tags(0).contains(&tag), "{}", tag);
+        }
+
+        // Only the PoS has a global model by default; the pronunciations of unknown words are
+        // composed from the subword dictionary.
+        assert!(model.tag_levels[0].model.is_some());
+        assert!(model.tag_levels[1].model.is_none());
+        assert!(model.tag_levels[1].language_model.is_some());
+        assert!(!model.subword_dictionary.is_empty());
+    }
+}
+
+#[cfg(feature = "ffi")]
+#[test]
+fn read_trained_models() {
+    use common::TempDir;
+    use kytea_tokenizer::ffi::{self, ModelFormat};
+
+    let dir = TempDir::new("read-models");
+    let binary_path = common::train(&dir, ModelFormat::Binary);
+    let text_path = common::train(&dir, ModelFormat::Text);
+    let binary = KyteaModel::open(&binary_path).unwrap();
+    let text = KyteaModel::open(&text_path).unwrap();
+    check_formats(&binary, &text);
+
+    let kytea = ffi::Model::try_new(&binary_path).unwrap();
+    for model in [&binary, &text] {
+        assert_eq!(model.encoding, kytea.encoding());
+        assert_eq!(model.num_tags(), kytea.num_tags());
+        assert_eq!(model.dictionary_size(), kytea.dictionary_size());
+        assert!(kytea
+            .tags(0)
+            .iter()
+            .all(|tag| model.tags(0).contains(&tag.as_str())));
+    }
+}
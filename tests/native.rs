@@ -0,0 +1,109 @@
+//! Compares the `native` backend with libkytea on a model trained from a few sentences.
+//!
+//! The model has a global model for the PoS only, so the pronunciations of unknown words are
+//! composed from the subword dictionary and scored by the language model.
+
+#![cfg(all(feature = "ffi", feature = "native"))]
+
+mod common;
+use common::TempDir;
+
+use kytea_tokenizer::ffi::{self, ModelFormat};
+use kytea_tokenizer::native;
+
+/// Sentences of words in the dictionary of the model.
+const KNOWN: &[&str] = &[
+    "",
+    "東京に行く",
+    "京都に住む",
+    "大阪へ行く",
+    "東京へ行く\n京都に行く\n\n大阪に住む\n",
+];
+
+/// Sentences with unknown words.
+const SENTENCES: &[&str] = &[
+    "東京都に住んでいます。",
+    "KyTeaは日本語の形態素解析器です。",
+    "２０２４年３月１日、ＡＢＣ社が発表した。",
+    "カタカナとひらがなと漢字とRomaji123が混ざった文",
+    "スペース を 含む 文\tとタブ",
+    "エスケープ/が\\必要な/文字",
+    "一行目\n二行目\n\n四行目\n",
+];
+
+fn models(dir: &TempDir, format: ModelFormat) -> (ffi::Model, native::Model) {
+    let path = common::train(dir, format);
+    let ffi = ffi::Model::try_new(&path).unwrap();
+    let native = native::Model::open(&path).unwrap();
+    (ffi, native)
+}
+
+#[test]
+fn same_output() {
+    let dir = TempDir::new("native-output");
+    for format in [ModelFormat::Binary, ModelFormat::Text] {
+        let (mut ffi, native) = models(&dir, format);
+        for sentence in KNOWN.iter().chain(SENTENCES) {
+            assert_eq!(
+                native.tokenize(sentence),
+                ffi.tokenize(sentence).unwrap(),
+                "{:?}",
+                sentence
+            );
+            assert_eq!(
+                native.tokenize_words(sentence),
+                ffi.tokenize_words(sentence).unwrap(),
+                "{:?}",
+                sentence
+            );
+        }
+    }
+}
+
+#[test]
+fn same_segmentation() {
+    let dir = TempDir::new("native-segmentation");
+    let (mut ffi, native) = models(&dir, ModelFormat::Binary);
+    let spans = |words: Vec<kytea_tokenizer::Word>| {
+        words
+            .into_iter()
+            .map(|word| (word.surface, word.offset_from, word.offset_to))
+            .collect::<Vec<_>>()
+    };
+    for sentence in KNOWN.iter().chain(SENTENCES) {
+        assert_eq!(
+            spans(native.tokenize_words(sentence)),
+            spans(ffi.tokenize_words(sentence).unwrap()),
+            "{:?}",
+            sentence
+        );
+    }
+}
+
+#[test]
+fn same_boundaries() {
+    let dir = TempDir::new("native-boundaries");
+    let (mut ffi, native) = models(&dir, ModelFormat::Binary);
+    for sentence in KNOWN.iter().chain(SENTENCES) {
+        let input = ffi::Istream::from_text(sentence);
+        let expected = ffi.tokenize_to_boundaries(&input).unwrap();
+        let actual = native.tokenize_to_boundaries(sentence);
+        assert_eq!(actual.len(), expected.len(), "{:?}", sentence);
+        for (actual, expected) in actual.iter().zip(&expected) {
+            assert_eq!(actual.offset, expected.offset, "{:?}", sentence);
+            assert_eq!(
+                actual.is_boundary(),
+                expected.is_boundary(),
+                "{:?}",
+                sentence
+            );
+            assert!(
+                (actual.confidence - expected.confidence).abs() < 1e-6,
+                "{:?}: {} != {}",
+                sentence,
+                actual.confidence,
+                expected.confidence
+            );
+        }
+    }
+}